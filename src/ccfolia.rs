@@ -0,0 +1,400 @@
+use std::convert::TryFrom;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::{json, Value};
+use tyche::dice::roller::Max;
+use tyche::Expr;
+
+use crate::commands::character::MAX_VALUE;
+use crate::commands::create_sheet::MAX_NAME_LENGTH;
+use crate::storage::character::Character;
+
+/// Characteristics shown as the status bars with their maximum values.
+/// `None` means the current value is the maximum.
+const STATUS_BARS: [(&str, Option<i32>); 4] = [
+    ("HP", None),
+    ("MP", None),
+    ("SAN", Some(99)),
+    ("Luck", Some(99)),
+];
+
+/// A character read from a CCFolia clipboard JSON.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ImportedCharacter {
+    pub name: String,
+    /// An identifier of the profile told by the roll commands (ex. `7th`).
+    /// `None` if the commands do not tell it.
+    pub system: Option<String>,
+    pub characteristics: Vec<(String, i32)>,
+    pub skills: Vec<(String, i32)>,
+}
+
+/// Matches a skill roll line such as `CCB<=60 【目星】` or `CC(1)<=60 Library Use`.
+static ROLL_LINE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(CCB|CC(?:\(-?\d+\))?|1d100)\s*<=\s*([^\s【]+)\s*(?:【(.+?)】|(\S.*?))?\s*$")
+        .unwrap()
+});
+
+/// Matches a reference to a parameter such as `{SAN}`.
+static REFERENCE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{([^}]+)\}").unwrap());
+
+/// Reads a character from a CCFolia clipboard JSON.
+/// The JSON exported by the sheet sites (ex. iachara, Character Sheet Warehouse) is also accepted.
+pub fn parse(json: &str) -> Result<ImportedCharacter, String> {
+    let root: Value =
+        serde_json::from_str(json).map_err(|err| format!("The JSON is invalid. ({})", err))?;
+
+    // The clipboard wraps the character with `{ "kind": "character", "data": ... }`.
+    let data = match root.get("data") {
+        Some(data) if data.is_object() => data,
+        _ => &root,
+    };
+
+    let name = data
+        .get("name")
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .ok_or_else(|| "The character does not have a name.".to_string())?
+        .to_string();
+
+    let mut characteristics: Vec<(String, i32)> = Vec::new();
+    for key in ["params", "status"].iter() {
+        let entries = data.get(key).and_then(Value::as_array);
+        for entry in entries.into_iter().flatten() {
+            let label = entry.get("label").and_then(Value::as_str);
+            let value = entry.get("value").and_then(as_int);
+            if let (Some(label), Some(value)) = (label, value) {
+                push_unique(&mut characteristics, label, value);
+            }
+        }
+    }
+
+    let commands = data.get("commands").and_then(Value::as_str).unwrap_or("");
+
+    let mut skills: Vec<(String, i32)> = Vec::new();
+    let mut has_6th = false;
+    let mut has_7th = false;
+    for line in commands.lines() {
+        let captures = match ROLL_LINE.captures(line) {
+            Some(captures) => captures,
+            None => continue,
+        };
+
+        let skill = match captures.get(3).or_else(|| captures.get(4)) {
+            Some(skill) => skill.as_str().trim(),
+            None => continue,
+        };
+        let chance = match evaluate(&captures[2], &characteristics) {
+            Some(chance) => chance,
+            None => continue,
+        };
+
+        match &captures[1] {
+            "CCB" => has_6th = true,
+            "1d100" => {}
+            _ => has_7th = true,
+        }
+
+        push_unique(&mut skills, skill, chance);
+    }
+
+    // A characteristic can also be written as a roll command (ex. `CC<=50 Luck`).
+    // It shares the key with the characteristic, so the skill is dropped.
+    skills.retain(|(skill, _)| {
+        !characteristics
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case(skill))
+    });
+
+    // `1d100` is used by every system, so only `CCB` and `CC` tell the system.
+    let system = match (has_6th, has_7th) {
+        (true, false) => Some("6th".to_string()),
+        (false, true) => Some("7th".to_string()),
+        _ => None,
+    };
+
+    Ok(ImportedCharacter {
+        name,
+        system,
+        characteristics,
+        skills,
+    })
+}
+
+/// Writes a character as a CCFolia clipboard JSON.
+/// The skills are written as the roll commands of the system.
+pub fn export(character: &Character) -> String {
+    let roll = match character.system.as_str() {
+        "6th" => "CCB",
+        "7th" => "CC",
+        _ => "1d100",
+    };
+
+    let is_status = |name: &str| {
+        STATUS_BARS
+            .iter()
+            .any(|(label, _)| label.eq_ignore_ascii_case(name))
+    };
+
+    let status = STATUS_BARS
+        .iter()
+        .filter_map(|(label, max)| {
+            let value = character.get_characteristic(label)?;
+            Some(json!({ "label": label, "value": value, "max": max.unwrap_or(value) }))
+        })
+        .collect::<Vec<_>>();
+    let params = character
+        .characteristics
+        .iter()
+        .filter(|(name, _)| !is_status(name))
+        .map(|(name, value)| json!({ "label": name, "value": value.to_string() }))
+        .collect::<Vec<_>>();
+    let commands = character
+        .skills
+        .iter()
+        .map(|(name, value)| format!("{}<={} 【{}】\n", roll, value, name))
+        .collect::<String>();
+
+    let clipboard = json!({
+        "kind": "character",
+        "data": {
+            "name": character.name,
+            "initiative": character.get_characteristic("DEX").unwrap_or(0),
+            "status": status,
+            "params": params,
+            "commands": commands,
+        },
+    });
+    serde_json::to_string_pretty(&clipboard).unwrap()
+}
+
+/// Reads an integer written as either a number or a string.
+/// A number with a fraction or out of the range of `i32` is not an integer.
+fn as_int(value: &Value) -> Option<i32> {
+    match value {
+        Value::Number(number) => number
+            .as_i64()
+            .and_then(|number| i32::try_from(number).ok()),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Adds the value unless the one with the same name (case-insensitive) already exists.
+/// The name and the value are capped as `/char set` does.
+fn push_unique(values: &mut Vec<(String, i32)>, name: &str, value: i32) {
+    let name = name
+        .trim()
+        .chars()
+        .take(MAX_NAME_LENGTH as usize)
+        .collect::<String>();
+    let value = value.clamp(0, MAX_VALUE as i32);
+    if !values
+        .iter()
+        .any(|(entry, _)| entry.eq_ignore_ascii_case(&name))
+    {
+        values.push((name, value));
+    }
+}
+
+/// Evaluates a target of the roll such as `{INT}*5` with the characteristics.
+fn evaluate(target: &str, characteristics: &[(String, i32)]) -> Option<i32> {
+    let mut unknown = false;
+    let target = REFERENCE.replace_all(target, |captures: &regex::Captures| match characteristics
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(&captures[1]))
+    {
+        Some((_, value)) => value.to_string(),
+        None => {
+            unknown = true;
+            String::new()
+        }
+    });
+    if unknown {
+        return None;
+    }
+
+    let expr: Expr = target.parse().ok()?;
+    expr.eval(&mut Max).ok()?.calc().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(values: &[(String, i32)], name: &str) -> Option<i32> {
+        values
+            .iter()
+            .find(|(entry, _)| entry == name)
+            .map(|(_, value)| *value)
+    }
+
+    #[test]
+    fn parse_6th() {
+        let character = parse(include_str!("../tests/fixtures/ccfolia_6th.json")).unwrap();
+
+        assert_eq!(character.name, "稲葉 翔太");
+        assert_eq!(character.system.as_deref(), Some("6th"));
+
+        // Params come first, followed by the status bars. Non-numeric ones are ignored.
+        let names = character
+            .characteristics
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec!["STR", "CON", "POW", "DEX", "APP", "SIZ", "INT", "EDU", "HP", "MP", "SAN"]
+        );
+        assert_eq!(value(&character.characteristics, "EDU"), Some(16));
+        assert_eq!(value(&character.characteristics, "SAN"), Some(65));
+
+        assert_eq!(value(&character.skills, "正気度ロール"), Some(65));
+        assert_eq!(value(&character.skills, "アイデア"), Some(75));
+        assert_eq!(value(&character.skills, "知識"), Some(80));
+        assert_eq!(value(&character.skills, "こぶし（パンチ）"), Some(50));
+        assert_eq!(value(&character.skills, "クトゥルフ神話"), Some(0));
+        assert_eq!(value(&character.skills, "STR × 5"), Some(55));
+
+        // The first one wins when the skill is duplicated.
+        assert_eq!(value(&character.skills, "目星"), Some(75));
+
+        // A damage roll is not a skill.
+        assert_eq!(value(&character.skills, "ダメージ判定"), None);
+        assert_eq!(character.skills.len(), 13);
+    }
+
+    #[test]
+    fn parse_7th() {
+        let character = parse(include_str!("../tests/fixtures/ccfolia_7th.json")).unwrap();
+
+        assert_eq!(character.name, "Harvey Walters");
+        assert_eq!(character.system.as_deref(), Some("7th"));
+
+        assert_eq!(value(&character.characteristics, "STR"), Some(45));
+        assert_eq!(value(&character.characteristics, "MOV"), Some(7));
+        assert_eq!(value(&character.characteristics, "Luck"), Some(50));
+
+        // Luck is also written as a roll command, but it is kept only as a characteristic.
+        assert_eq!(
+            character.skills,
+            vec![
+                ("SAN Check".to_string(), 60),
+                ("Spot Hidden".to_string(), 70),
+                ("Library Use".to_string(), 60),
+                ("Listen".to_string(), 30),
+            ]
+        );
+    }
+
+    #[test]
+    fn insert_fixtures() {
+        fn as_str(values: &[(String, i32)]) -> Vec<(&str, i32)> {
+            values
+                .iter()
+                .map(|(name, value)| (name.as_str(), *value))
+                .collect()
+        }
+
+        let mut connection = crate::storage::open_in_memory();
+        let fixtures = [
+            include_str!("../tests/fixtures/ccfolia_6th.json"),
+            include_str!("../tests/fixtures/ccfolia_7th.json"),
+        ];
+        for json in fixtures.iter() {
+            let imported = parse(json).unwrap();
+            let character = Character::insert(
+                &mut connection,
+                serenity::model::id::UserId::new(1),
+                &imported.name,
+                imported.system.as_deref().unwrap(),
+                &as_str(&imported.characteristics),
+                &as_str(&imported.skills),
+            )
+            .unwrap()
+            .unwrap();
+            assert_eq!(character.characteristics, imported.characteristics);
+            assert_eq!(character.skills, imported.skills);
+        }
+    }
+
+    #[test]
+    fn cap_values_and_names() {
+        let long_name = "A".repeat(2000);
+        let json = json!({
+            "name": "A",
+            "params": [
+                { "label": "STR", "value": 4294967346u64 },
+                { "label": "CON", "value": 12.5 },
+                { "label": "POW", "value": "-5" },
+                { "label": long_name, "value": 10 },
+            ],
+            "commands": "1d100<=5000 【Dodge】",
+        });
+        let character = parse(&json.to_string()).unwrap();
+
+        // The values which are not `i32` are ignored, and the others are capped.
+        assert_eq!(
+            character.characteristics,
+            vec![
+                ("POW".to_string(), 0),
+                ("A".repeat(MAX_NAME_LENGTH as usize), 10),
+            ]
+        );
+        assert_eq!(character.skills, vec![("Dodge".to_string(), 999)]);
+
+        // `1d100` does not tell the system.
+        assert_eq!(character.system, None);
+    }
+
+    #[test]
+    fn parse_unwrapped_data() {
+        let character = parse(r#"{ "name": "A", "commands": "CCB<=40 【回避】" }"#).unwrap();
+        assert_eq!(character.name, "A");
+        assert_eq!(character.skills, vec![("回避".to_string(), 40)]);
+    }
+
+    #[test]
+    fn export_and_import() {
+        let character = Character {
+            id: 1,
+            owner_id: serenity::model::id::UserId::new(1),
+            name: "Harvey Walters".to_string(),
+            system: "7th".to_string(),
+            characteristics: vec![
+                ("STR".to_string(), 45),
+                ("HP".to_string(), 10),
+                ("DEX".to_string(), 40),
+                ("SAN".to_string(), 60),
+            ],
+            skills: vec![("Spot Hidden".to_string(), 70), ("図書館".to_string(), 60)],
+        };
+
+        let exported = export(&character);
+        assert!(exported.contains("CC<=70 【Spot Hidden】"));
+
+        let imported = parse(&exported).unwrap();
+        assert_eq!(imported.name, character.name);
+        assert_eq!(imported.system.as_deref(), Some(character.system.as_str()));
+        assert_eq!(imported.skills, character.skills);
+
+        // The status bars are read after the params.
+        assert_eq!(
+            imported.characteristics,
+            vec![
+                ("STR".to_string(), 45),
+                ("DEX".to_string(), 40),
+                ("HP".to_string(), 10),
+                ("SAN".to_string(), 60),
+            ]
+        );
+    }
+
+    #[test]
+    fn reject_invalid_json() {
+        assert!(parse("CCB<=60 【目星】").is_err());
+        assert!(parse(r#"{ "kind": "character", "data": { "name": "" } }"#).is_err());
+    }
+}
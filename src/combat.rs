@@ -0,0 +1,275 @@
+use serde::{Deserialize, Serialize};
+
+/// A bonus to the initiative of the ones who have readied firearms.
+pub const FIREARM_BONUS: i32 = 50;
+
+/// A participant of a combat.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Participant {
+    pub name: String,
+    pub dex: i32,
+    /// Whether the participant has readied a firearm.
+    pub firearm: bool,
+    /// Whether the participant is surprised. A surprised one loses the turn of the first round.
+    pub surprised: bool,
+    /// Whether the participant is delaying the turn.
+    pub delayed: bool,
+    /// Whether the participant has acted in the round.
+    pub acted: bool,
+}
+
+impl Participant {
+    pub fn new(name: &str, dex: i32, firearm: bool, surprised: bool) -> Participant {
+        Participant {
+            name: name.to_string(),
+            dex,
+            firearm,
+            surprised,
+            delayed: false,
+            acted: false,
+        }
+    }
+
+    /// Gets the initiative of the participant following Call of Cthulhu 7th Edition.
+    pub fn initiative(&self) -> i32 {
+        if self.firearm {
+            self.dex + FIREARM_BONUS
+        } else {
+            self.dex
+        }
+    }
+}
+
+/// A state of a combat following Call of Cthulhu 7th Edition.
+/// It is saved as a JSON between the commands.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Combat {
+    pub round: u32,
+    /// The participants in the order they act in the round.
+    pub order: Vec<Participant>,
+}
+
+impl Default for Combat {
+    fn default() -> Self {
+        Combat {
+            round: 1,
+            order: Vec::new(),
+        }
+    }
+}
+
+impl Combat {
+    /// Adds a participant. The one acts at the place of the initiative among the ones who have not acted.
+    pub fn add(&mut self, participant: Participant) -> Result<(), String> {
+        if self.find(&participant.name).is_some() {
+            return Err(format!(
+                "\"{}\" is already in the combat.",
+                participant.name
+            ));
+        }
+
+        let index = self
+            .order
+            .iter()
+            .position(|other| {
+                !other.acted && !other.delayed && other.initiative() < participant.initiative()
+            })
+            .unwrap_or_else(|| {
+                // Put it before the delaying ones, who wait for everyone else.
+                self.order
+                    .iter()
+                    .rposition(|other| !other.delayed)
+                    .map_or(0, |index| index + 1)
+            });
+        self.order.insert(index, participant);
+        Ok(())
+    }
+
+    /// Removes a participant.
+    pub fn remove(&mut self, name: &str) -> Result<Participant, String> {
+        match self.find(name) {
+            Some(index) => Ok(self.order.remove(index)),
+            None => Err(format!("\"{}\" is not in the combat.", name)),
+        }
+    }
+
+    /// Gets the index of the participant whose turn it is.
+    /// Returns `None` if everyone has acted in the round.
+    pub fn current(&self) -> Option<usize> {
+        self.order
+            .iter()
+            .position(|participant| !participant.acted && !self.loses_turn(participant))
+    }
+
+    /// Checks whether the participant loses the turn because of surprise.
+    pub fn loses_turn(&self, participant: &Participant) -> bool {
+        self.round == 1 && participant.surprised
+    }
+
+    /// Ends the current turn. When everyone has acted, the next round starts.
+    pub fn next_turn(&mut self) {
+        match self.current() {
+            Some(index) => {
+                self.order[index].acted = true;
+                if self.current().is_none() {
+                    self.next_round();
+                }
+            }
+            None => self.next_round(),
+        }
+    }
+
+    /// Starts the next round. The order is sorted by the initiative again.
+    pub fn next_round(&mut self) {
+        self.round += 1;
+        for participant in self.order.iter_mut() {
+            participant.acted = false;
+            participant.delayed = false;
+        }
+        // The sort is stable, so the ones who were added earlier win the ties.
+        self.order
+            .sort_by_key(|participant| std::cmp::Reverse(participant.initiative()));
+    }
+
+    /// Makes the current participant delay the turn until everyone else has acted.
+    pub fn delay(&mut self) -> Result<(), String> {
+        let index = match self.current() {
+            Some(index) => index,
+            None => return Err("Everyone has acted in this round.".to_string()),
+        };
+
+        let waiting = self
+            .order
+            .iter()
+            .skip(index + 1)
+            .any(|participant| !participant.acted && !self.loses_turn(participant));
+        if !waiting {
+            return Err(format!(
+                "{} is the last one to act in this round.",
+                self.order[index].name
+            ));
+        }
+
+        let mut participant = self.order.remove(index);
+        participant.delayed = true;
+        self.order.push(participant);
+        Ok(())
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.order
+            .iter()
+            .position(|participant| participant.name.eq_ignore_ascii_case(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(combat: &Combat) -> Vec<&str> {
+        combat
+            .order
+            .iter()
+            .map(|participant| participant.name.as_str())
+            .collect()
+    }
+
+    fn current(combat: &Combat) -> Option<&str> {
+        combat
+            .current()
+            .map(|index| combat.order[index].name.as_str())
+    }
+
+    fn combat() -> Combat {
+        let mut combat = Combat::default();
+        combat
+            .add(Participant::new("Harvey", 40, false, false))
+            .unwrap();
+        combat
+            .add(Participant::new("Cultist", 60, false, false))
+            .unwrap();
+        combat
+            .add(Participant::new("Gunman", 30, true, false))
+            .unwrap();
+        combat
+            .add(Participant::new("Ghoul", 50, false, true))
+            .unwrap();
+        combat
+    }
+
+    #[test]
+    fn sorted_by_initiative() {
+        let mut combat = combat();
+        assert_eq!(names(&combat), vec!["Gunman", "Cultist", "Ghoul", "Harvey"]);
+        assert!(combat
+            .add(Participant::new("harvey", 10, false, false))
+            .is_err());
+
+        // The surprised one loses the turn of the first round.
+        assert_eq!(current(&combat), Some("Gunman"));
+        combat.next_turn();
+        assert_eq!(current(&combat), Some("Cultist"));
+        combat.next_turn();
+        assert_eq!(current(&combat), Some("Harvey"));
+        combat.next_turn();
+
+        assert_eq!(combat.round, 2);
+        assert_eq!(current(&combat), Some("Gunman"));
+        combat.next_turn();
+        combat.next_turn();
+        assert_eq!(current(&combat), Some("Ghoul"));
+    }
+
+    #[test]
+    fn delay_until_the_others_act() {
+        let mut combat = combat();
+        combat.delay().unwrap();
+        assert_eq!(names(&combat), vec!["Cultist", "Ghoul", "Harvey", "Gunman"]);
+        assert_eq!(current(&combat), Some("Cultist"));
+
+        // A newcomer acts before the delaying one.
+        combat
+            .add(Participant::new("Dog", 70, false, false))
+            .unwrap();
+        assert_eq!(current(&combat), Some("Dog"));
+        combat
+            .add(Participant::new("Cat", 5, false, false))
+            .unwrap();
+        assert_eq!(
+            names(&combat),
+            vec!["Dog", "Cultist", "Ghoul", "Harvey", "Cat", "Gunman"]
+        );
+
+        combat.next_turn();
+        combat.next_turn();
+        combat.next_turn();
+        combat.next_turn();
+        assert_eq!(current(&combat), Some("Gunman"));
+        assert!(combat.delay().is_err());
+
+        // The order is restored in the next round.
+        combat.next_round();
+        assert_eq!(
+            names(&combat),
+            vec!["Gunman", "Dog", "Cultist", "Ghoul", "Harvey", "Cat"]
+        );
+        assert!(combat.order.iter().all(|participant| !participant.delayed));
+    }
+
+    #[test]
+    fn remove_participant() {
+        let mut combat = combat();
+        combat.remove("gunman").unwrap();
+        assert_eq!(current(&combat), Some("Cultist"));
+        assert!(combat.remove("Gunman").is_err());
+    }
+
+    #[test]
+    fn saved_as_json() {
+        let mut combat = combat();
+        combat.next_turn();
+        let json = serde_json::to_string(&combat).unwrap();
+        assert_eq!(serde_json::from_str::<Combat>(&json).unwrap(), combat);
+    }
+}
@@ -0,0 +1,538 @@
+use anyhow::Result;
+use serenity::builder::{CreateAttachment, CreateCommand, CreateCommandOption, CreateEmbed};
+use serenity::model::application::{CommandInteraction, CommandOptionType};
+use serenity::prelude::Context;
+
+use crate::ccfolia;
+use crate::commands::create_sheet::{CSCommand, MAX_NAME_LENGTH};
+use crate::commands::settings::SettingsCommand;
+use crate::commands::{BotCommand, CommandStatus, InteractionUtil, SendEmbed};
+use crate::storage::character::{Character, ValueKind};
+use crate::storage::Storage;
+
+/// The maximum length of a value of an embed field.
+const MAX_FIELD_LENGTH: usize = 1024;
+
+/// The maximum size of a file to import.
+const MAX_IMPORT_SIZE: u32 = 256 * 1024;
+
+/// The maximum value of a characteristic or a skill.
+pub const MAX_VALUE: u64 = 999;
+
+/// A command that manages the characters saved by `/cs`.
+pub struct CharCommand;
+
+impl CharCommand {
+    /// Creates an option to designate a character.
+    pub fn character_option(required: bool) -> CreateCommandOption {
+        let (description, description_ja) = if required {
+            ("A name of the character", "キャラクターの名前")
+        } else {
+            (
+                "A name of the character (default: the active one)",
+                "キャラクターの名前 (既定: 使用中のキャラクター)",
+            )
+        };
+        CreateCommandOption::new(CommandOptionType::String, "character", description)
+            .name_localized("ja", "キャラクター")
+            .description_localized("ja", description_ja)
+            .max_length(MAX_NAME_LENGTH)
+            .required(required)
+    }
+
+    /// Finds the character designated by the option, or the active one if it is omitted.
+    pub async fn find_character(
+        interaction: &CommandInteraction,
+    ) -> Result<std::result::Result<Character, String>> {
+        let owner_id = interaction.user.id;
+        let name = interaction.get_string_option("character".into());
+
+        let character = Storage::with(|connection| match name {
+            Some(name) => Character::find(connection, owner_id, name),
+            None => Character::active(connection, owner_id),
+        })
+        .await?;
+
+        Ok(character.ok_or_else(|| match name {
+            Some(name) => format!("You do not have a character named \"{}\".", name),
+            None => "You do not have an active character. Create one with /cs.".to_string(),
+        }))
+    }
+
+    async fn execute_set(ctx: &Context, interaction: &CommandInteraction) -> Result<CommandStatus> {
+        let key = interaction.get_string_option("key".into()).unwrap().trim();
+        let value = interaction.get_int_option("value".into()).unwrap();
+
+        if key.is_empty() {
+            return Ok(CommandStatus::Err(
+                "The name must not be empty.".to_string(),
+            ));
+        }
+
+        let mut character = match CharCommand::find_character(interaction).await? {
+            Ok(character) => character,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+
+        let old = character
+            .get_characteristic(key)
+            .or_else(|| character.get_skill(key));
+        let kind = Storage::with(|connection| character.set_value(connection, key, value)).await?;
+
+        let kind = match kind {
+            ValueKind::Characteristic => "characteristic",
+            ValueKind::Skill => "skill",
+        };
+        let change = match old {
+            Some(old) => format!("{} -> {}", old, value),
+            None => format!("(new) -> {}", value),
+        };
+
+        let embed = CreateEmbed::new()
+            .title(format!(":pencil: {} of {}", key, character.name))
+            .field(format!("Updated the {}", kind), change, false);
+
+        interaction.send_embed(ctx, embed).await?;
+
+        Ok(CommandStatus::Ok)
+    }
+
+    async fn execute_show(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let character = match CharCommand::find_character(interaction).await? {
+            Ok(character) => character,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+
+        let system = CSCommand::system_name(&character.system).unwrap_or(&character.system);
+        let embed = CreateEmbed::new()
+            .title(format!(":scroll: {}", character.name))
+            .description(system);
+
+        let embed = CharCommand::fields(&character.characteristics, " / ")
+            .into_iter()
+            .fold(embed, |embed, value| {
+                embed.field(":bar_chart: Characteristics", value, false)
+            });
+        let embed = CharCommand::fields(&character.skills, "\n")
+            .into_iter()
+            .fold(embed, |embed, value| {
+                embed.field(":books: Skills", value, false)
+            });
+
+        interaction.send_embed(ctx, embed).await?;
+
+        Ok(CommandStatus::Ok)
+    }
+
+    async fn execute_list(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let owner_id = interaction.user.id;
+        let names = Storage::with(|connection| Character::list(connection, owner_id)).await?;
+
+        if names.is_empty() {
+            return Ok(CommandStatus::Err(
+                "You do not have any characters. Create one with /cs.".to_string(),
+            ));
+        }
+
+        let names = names
+            .into_iter()
+            .map(|(name, active)| {
+                if active {
+                    format!(":arrow_forward: **{}**", name)
+                } else {
+                    format!(":white_small_square: {}", name)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let embed = CreateEmbed::new()
+            .title(format!("{}'s characters", interaction.get_nickname()))
+            .description(names);
+
+        interaction.send_embed(ctx, embed).await?;
+
+        Ok(CommandStatus::Ok)
+    }
+
+    async fn execute_use(ctx: &Context, interaction: &CommandInteraction) -> Result<CommandStatus> {
+        let owner_id = interaction.user.id;
+        let name = interaction.get_string_option("character".into()).unwrap();
+
+        let activated =
+            Storage::with(|connection| Character::activate(connection, owner_id, name)).await?;
+        if !activated {
+            return Ok(CommandStatus::Err(format!(
+                "You do not have a character named \"{}\".",
+                name
+            )));
+        }
+
+        let embed = CreateEmbed::new().title(format!(":arrow_forward: Now playing {}", name));
+
+        interaction.send_embed(ctx, embed).await?;
+
+        Ok(CommandStatus::Ok)
+    }
+
+    async fn execute_delete(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let owner_id = interaction.user.id;
+        let name = interaction.get_string_option("character".into()).unwrap();
+
+        let deleted =
+            Storage::with(|connection| Character::delete(connection, owner_id, name)).await?;
+        if !deleted {
+            return Ok(CommandStatus::Err(format!(
+                "You do not have a character named \"{}\".",
+                name
+            )));
+        }
+
+        let embed = CreateEmbed::new().title(format!(":wastebasket: Deleted {}", name));
+
+        interaction.send_embed(ctx, embed).await?;
+
+        Ok(CommandStatus::Ok)
+    }
+
+    async fn execute_import(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let file = interaction.get_attachment_option("file".into());
+        let json = interaction.get_string_option("json".into());
+
+        let json = match (file, json) {
+            (Some(file), None) => {
+                if file.size > MAX_IMPORT_SIZE {
+                    return Ok(CommandStatus::Err(format!(
+                        "The file must be smaller than {} KB.",
+                        MAX_IMPORT_SIZE / 1024
+                    )));
+                }
+                match String::from_utf8(file.download().await?) {
+                    Ok(json) => json,
+                    Err(_) => {
+                        return Ok(CommandStatus::Err(
+                            "The file must be encoded in UTF-8.".to_string(),
+                        ))
+                    }
+                }
+            }
+            (None, Some(json)) => json.to_string(),
+            _ => {
+                return Ok(CommandStatus::Err(
+                    "Attach a file or paste the JSON.".to_string(),
+                ))
+            }
+        };
+
+        let imported = match ccfolia::parse(&json) {
+            Ok(imported) => imported,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+
+        let name = interaction
+            .get_string_option("name".into())
+            .unwrap_or(&imported.name);
+        if name.chars().count() > MAX_NAME_LENGTH as usize {
+            return Ok(CommandStatus::Err(format!(
+                "The name must be {} characters or fewer. Designate another name.",
+                MAX_NAME_LENGTH
+            )));
+        }
+
+        // The export does not always tell the system, so the one of this server is used then.
+        let system = match imported.system {
+            Some(system) => system,
+            None => SettingsCommand::system_of(interaction.guild_id)
+                .await?
+                .id()
+                .to_string(),
+        };
+
+        let characteristics = imported
+            .characteristics
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value))
+            .collect::<Vec<_>>();
+        let skills = imported
+            .skills
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value))
+            .collect::<Vec<_>>();
+
+        let owner_id = interaction.user.id;
+        let character = Storage::with(|connection| {
+            Character::insert(
+                connection,
+                owner_id,
+                name,
+                &system,
+                &characteristics,
+                &skills,
+            )
+        })
+        .await?;
+        if character.is_none() {
+            return Ok(CommandStatus::Err(format!(
+                "You already have a character named \"{}\".",
+                name
+            )));
+        }
+
+        let embed = CreateEmbed::new()
+            .title(format!(":inbox_tray: Imported {}", name))
+            .description(CSCommand::system_name(&system).unwrap_or(&system))
+            .field(
+                ":bar_chart: Characteristics",
+                characteristics.len().to_string(),
+                true,
+            )
+            .field(":books: Skills", skills.len().to_string(), true);
+
+        interaction.send_embed(ctx, embed).await?;
+
+        Ok(CommandStatus::Ok)
+    }
+
+    async fn execute_export(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let character = match CharCommand::find_character(interaction).await? {
+            Ok(character) => character,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+
+        let file_name = CharCommand::file_name(&character.name);
+        let files = vec![
+            CreateAttachment::bytes(ccfolia::export(&character), format!("{}.json", file_name)),
+            CreateAttachment::bytes(
+                CharCommand::markdown(&character),
+                format!("{}.md", file_name),
+            ),
+        ];
+
+        let embed = CreateEmbed::new()
+            .title(format!(":outbox_tray: Exported {}", character.name))
+            .field(
+                "CCFolia",
+                format!(
+                    "Paste the content of `{}.json` onto the board of CCFolia.",
+                    file_name
+                ),
+                false,
+            )
+            .field(
+                "Markdown",
+                format!("`{}.md` is a sheet to read.", file_name),
+                false,
+            );
+
+        interaction.send_embed_with_files(ctx, embed, files).await?;
+
+        Ok(CommandStatus::Ok)
+    }
+
+    /// Makes a name of the character safe to use as a file name.
+    fn file_name(name: &str) -> String {
+        name.chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect()
+    }
+
+    /// Writes the character as a Markdown sheet.
+    fn markdown(character: &Character) -> String {
+        let system = CSCommand::system_name(&character.system).unwrap_or(&character.system);
+
+        let table = |values: &[(String, i32)]| {
+            values
+                .iter()
+                .map(|(name, value)| format!("|{}|{}|\n", name.replace('|', "\\|"), value))
+                .collect::<String>()
+        };
+
+        format!(
+            "# {}\n\n{}\n\n## Characteristics\n\n|Name|Value|\n|:---|---:|\n{}\n## Skills\n\n|Name|Value|\n|:---|---:|\n{}",
+            character.name,
+            system,
+            table(&character.characteristics),
+            table(&character.skills)
+        )
+    }
+
+    /// Formats the values into the texts each of which fits in an embed field.
+    fn fields(values: &[(String, i32)], separator: &str) -> Vec<String> {
+        let mut fields: Vec<String> = Vec::new();
+        for (name, value) in values {
+            let entry = format!("{} {}", name, value);
+            match fields.last_mut() {
+                Some(field) if field.len() + separator.len() + entry.len() <= MAX_FIELD_LENGTH => {
+                    field.push_str(separator);
+                    field.push_str(&entry);
+                }
+                _ => fields.push(entry),
+            }
+        }
+        fields
+    }
+}
+
+#[naming]
+#[serenity::async_trait]
+impl BotCommand for CharCommand {
+    fn create(&self) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description("Manages your characters.")
+            .description_localized("ja", "キャラクターを管理します.")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "set",
+                    "Sets a characteristic or a skill of the character.",
+                )
+                .description_localized("ja", "キャラクターの能力値や技能を設定します.")
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "key",
+                        "A name of the characteristic or the skill (ex. STR, Spot Hidden)",
+                    )
+                    .name_localized("ja", "項目")
+                    .description_localized("ja", "能力値や技能の名前 (例: STR, 目星)")
+                    .max_length(MAX_NAME_LENGTH)
+                    .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Integer, "value", "A value")
+                        .name_localized("ja", "値")
+                        .description_localized("ja", "値")
+                        .min_int_value(0)
+                        .max_int_value(MAX_VALUE)
+                        .required(true),
+                )
+                .add_sub_option(CharCommand::character_option(false)),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "show",
+                    "Shows the character.",
+                )
+                .description_localized("ja", "キャラクターを表示します.")
+                .add_sub_option(CharCommand::character_option(false)),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "list",
+                    "Lists your characters.",
+                )
+                .description_localized("ja", "キャラクターの一覧を表示します."),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "use",
+                    "Makes the character active.",
+                )
+                .description_localized("ja", "使用するキャラクターを切り替えます.")
+                .add_sub_option(CharCommand::character_option(true)),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "delete",
+                    "Deletes the character.",
+                )
+                .description_localized("ja", "キャラクターを削除します.")
+                .add_sub_option(CharCommand::character_option(true)),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "import",
+                    "Imports a character from a CCFolia clipboard JSON.",
+                )
+                .description_localized(
+                    "ja",
+                    "ココフォリアのクリップボード用JSONからキャラクターを読み込みます.",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Attachment,
+                        "file",
+                        "A JSON file exported by the sheet site",
+                    )
+                    .name_localized("ja", "ファイル")
+                    .description_localized(
+                        "ja",
+                        "キャラクターシートのサイトから出力したJSONファイル",
+                    ),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "json",
+                        "A pasted JSON (Use this instead of the file.)",
+                    )
+                    .description_localized("ja", "貼り付けたJSON (ファイルの代わりに使います.)"),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "name",
+                        "A name to save the character as (default: the name in the JSON)",
+                    )
+                    .name_localized("ja", "名前")
+                    .description_localized("ja", "キャラクターを保存する名前 (既定: JSON内の名前)")
+                    .max_length(MAX_NAME_LENGTH),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "export",
+                    "Exports the character as a CCFolia clipboard JSON and a Markdown sheet.",
+                )
+                .description_localized(
+                    "ja",
+                    "キャラクターをココフォリアのクリップボード用JSONとMarkdownのシートに書き出します.",
+                )
+                .add_sub_option(CharCommand::character_option(false)),
+            )
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        match interaction.get_subcommand() {
+            Some("set") => CharCommand::execute_set(ctx, interaction).await,
+            Some("show") => CharCommand::execute_show(ctx, interaction).await,
+            Some("list") => CharCommand::execute_list(ctx, interaction).await,
+            Some("use") => CharCommand::execute_use(ctx, interaction).await,
+            Some("delete") => CharCommand::execute_delete(ctx, interaction).await,
+            Some("import") => CharCommand::execute_import(ctx, interaction).await,
+            Some("export") => CharCommand::execute_export(ctx, interaction).await,
+            _ => Ok(CommandStatus::Err("Unknown subcommand.".to_string())),
+        }
+    }
+}
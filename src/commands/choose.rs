@@ -1,14 +1,143 @@
 use anyhow::Result;
+use once_cell::sync::Lazy;
 use rand::Rng;
+use regex::Regex;
 use serenity::builder::{CreateCommand, CreateCommandOption, CreateEmbed};
 use serenity::model::application::{CommandInteraction, CommandOptionType};
 use serenity::prelude::Context;
 
+use crate::commands::session::SessionCommand;
 use crate::commands::{BotCommand, CommandStatus, InteractionUtil, SendEmbed};
+use crate::dice::DiceSource;
+use crate::storage::session::RollRecord;
+
+/// The maximum number of choices.
+const MAX_CHOICES: usize = 100;
+
+/// The characters separating the choices.
+const SEPARATORS: [char; 3] = [',', '、', '，'];
+
+/// Matches a weight put on a choice such as `A*3`.
+static WEIGHT: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(.*?)\s*\*\s*(\d+)$").unwrap());
 
 /// A command that make a random choice.
 pub struct ChooseCommand;
 
+/// A choice with its weight.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Choice {
+    name: String,
+    weight: u32,
+}
+
+impl Choice {
+    /// Shows the choice with its weight if it is not 1.
+    fn label(&self) -> String {
+        if self.weight == 1 {
+            self.name.clone()
+        } else {
+            format!("{}*{}", self.name, self.weight)
+        }
+    }
+}
+
+impl ChooseCommand {
+    /// Reads the choices separated by `,`, `、` or `，`.
+    /// A choice can be quoted with `"` to contain the separators, and weighted like `A*3`.
+    fn parse(text: &str) -> std::result::Result<Vec<Choice>, String> {
+        let mut items = Vec::new();
+        let mut item = String::new();
+        let mut quoted = false;
+        for c in text.chars() {
+            match c {
+                '"' => {
+                    quoted = !quoted;
+                    item.push(c);
+                }
+                c if !quoted && SEPARATORS.contains(&c) => items.push(std::mem::take(&mut item)),
+                c => item.push(c),
+            }
+        }
+        if quoted {
+            return Err("A quote is not closed.".to_string());
+        }
+        items.push(item);
+
+        if items.len() > MAX_CHOICES {
+            return Err(format!("Give up to {} choices.", MAX_CHOICES));
+        }
+
+        items
+            .iter()
+            .map(|item| {
+                let item = item.trim();
+                let (name, weight) = match WEIGHT.captures(item) {
+                    Some(captures) => {
+                        let weight = captures[2]
+                            .parse::<u32>()
+                            .ok()
+                            .filter(|weight| *weight > 0)
+                            .ok_or_else(|| {
+                                format!("The weight of \"{}\" must be positive.", item)
+                            })?;
+                        (captures.get(1).unwrap().as_str(), weight)
+                    }
+                    None => (item, 1),
+                };
+
+                let name = match name
+                    .strip_prefix('"')
+                    .and_then(|name| name.strip_suffix('"'))
+                {
+                    Some(name) => name,
+                    None if name.contains('"') => {
+                        return Err(format!("Quote the whole choice: {}", item));
+                    }
+                    None => name,
+                };
+                if name.trim().is_empty() {
+                    return Err(
+                        "An empty choice is found. Remove the extra separators.".to_string()
+                    );
+                }
+
+                Ok(Choice {
+                    name: name.to_string(),
+                    weight,
+                })
+            })
+            .collect()
+    }
+
+    /// Draws `count` choices without replacement. The heavier ones tend to be drawn earlier.
+    /// Returns the indices of the drawn ones in order.
+    fn draw(choices: &[Choice], count: usize, rng: &mut impl Rng) -> Vec<usize> {
+        let mut remaining = (0..choices.len()).collect::<Vec<_>>();
+        let mut drawn = Vec::new();
+        while drawn.len() < count && !remaining.is_empty() {
+            let total: u64 = remaining
+                .iter()
+                .map(|index| choices[*index].weight as u64)
+                .sum();
+            let mut selected = rng.gen_range(0..total);
+            let position = remaining
+                .iter()
+                .position(|index| {
+                    let weight = choices[*index].weight as u64;
+                    if selected < weight {
+                        true
+                    } else {
+                        selected -= weight;
+                        false
+                    }
+                })
+                .unwrap();
+            drawn.push(remaining.remove(position));
+        }
+        drawn
+    }
+}
+
 #[naming]
 #[serenity::async_trait]
 impl BotCommand for ChooseCommand {
@@ -20,12 +149,38 @@ impl BotCommand for ChooseCommand {
                 CreateCommandOption::new(
                     CommandOptionType::String,
                     "choices",
-                    "Comma-separated choices (ex. A,B,C)",
+                    "Comma-separated choices (ex. A,B*2,\"C, D\")",
                 )
                 .name_localized("ja", "選択肢")
-                .description_localized("ja", "カンマ区切りで与えてください (例: A,B,C)")
+                .description_localized(
+                    "ja",
+                    "カンマか読点で区切って与えてください (例: A、B*2、\"C, D\")",
+                )
                 .required(true),
             )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "count",
+                    "The number of choices to draw without replacement (default: 1)",
+                )
+                .name_localized("ja", "個数")
+                .description_localized("ja", "重複なしで選ぶ個数 (既定: 1)")
+                .min_int_value(1)
+                .max_int_value(MAX_CHOICES as u64),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "shuffle",
+                    "Whether to put all of the choices in a random order (ex. for the turn order)",
+                )
+                .name_localized("ja", "シャッフル")
+                .description_localized(
+                    "ja",
+                    "すべての選択肢をランダムに並べ替えるかどうか (例: 手番の順番)",
+                ),
+            )
     }
 
     async fn execute(
@@ -33,29 +188,144 @@ impl BotCommand for ChooseCommand {
         ctx: &Context,
         interaction: &CommandInteraction,
     ) -> Result<CommandStatus> {
-        let choices: Vec<&str> = interaction
-            .get_string_option("choices".into())
-            .unwrap()
-            .split(",")
-            .collect();
+        let choices =
+            match ChooseCommand::parse(interaction.get_string_option("choices".into()).unwrap()) {
+                Ok(choices) => choices,
+                Err(message) => return Ok(CommandStatus::Err(message)),
+            };
+
+        let shuffle = interaction.get_bool_option("shuffle".into()) == Some(true);
+        let count = interaction.get_int_option("count".into());
+        let count = match (shuffle, count) {
+            (true, Some(_)) => {
+                return Ok(CommandStatus::Err(
+                    "`count` cannot be used with `shuffle`.".to_string(),
+                ))
+            }
+            (true, None) => choices.len(),
+            (false, Some(count)) if count as usize > choices.len() => {
+                return Ok(CommandStatus::Err(format!(
+                    "Cannot draw {} from {} choices.",
+                    count,
+                    choices.len()
+                )))
+            }
+            (false, Some(count)) => count.max(1) as usize,
+            (false, None) => 1,
+        };
 
         let author = interaction.get_nickname();
 
-        let selected = rand::thread_rng().gen_range(0..choices.len());
-
-        interaction
-            .send_embed(
-                ctx,
-                CreateEmbed::new()
-                    .title(format!("{}'s choice", author))
-                    .field(
-                        format!("**{}**", choices[selected]),
-                        format!("From {}", choices.join(",")),
-                        false,
-                    ),
-            )
-            .await?;
+        let drawn = DiceSource::with_guild(interaction.guild_id, |dice| {
+            ChooseCommand::draw(&choices, count, dice)
+        })
+        .await?;
+
+        let all = choices
+            .iter()
+            .map(Choice::label)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let names = drawn
+            .iter()
+            .map(|index| choices[*index].name.as_str())
+            .collect::<Vec<_>>();
+
+        let record = RollRecord {
+            roller: author.clone(),
+            expression: if shuffle {
+                format!("Shuffle {}", all)
+            } else {
+                format!("Choose {} from {}", count, all)
+            },
+            dice: drawn
+                .iter()
+                .map(|index| format!("{}/{}", index + 1, choices.len()))
+                .collect::<Vec<_>>()
+                .join(", "),
+            result: names.join(", "),
+            tier: None,
+        };
+        SessionCommand::record_command(interaction, &[record]).await?;
+
+        let embed = if names.len() == 1 {
+            CreateEmbed::new()
+                .title(format!("{}'s choice", author))
+                .field(format!("**{}**", names[0]), format!("From {}", all), false)
+        } else {
+            let order = names
+                .iter()
+                .enumerate()
+                .map(|(index, name)| format!("{}. **{}**", index + 1, name))
+                .collect::<Vec<_>>()
+                .join("\n");
+            CreateEmbed::new()
+                .title(if shuffle {
+                    format!("{}'s shuffle", author)
+                } else {
+                    format!("{}'s choices", author)
+                })
+                .description(order)
+                .field("From", all, false)
+        };
+
+        interaction.send_embed(ctx, embed).await?;
 
         Ok(CommandStatus::Ok)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn choice(name: &str, weight: u32) -> Choice {
+        Choice {
+            name: name.to_string(),
+            weight,
+        }
+    }
+
+    #[test]
+    fn parse_choices() {
+        assert_eq!(
+            ChooseCommand::parse(" A , B*3、C，\"D, E\" * 2").unwrap(),
+            vec![
+                choice("A", 1),
+                choice("B", 3),
+                choice("C", 1),
+                choice("D, E", 2)
+            ]
+        );
+        assert_eq!(
+            ChooseCommand::parse("\"x*2\",y").unwrap(),
+            vec![choice("x*2", 1), choice("y", 1)]
+        );
+
+        assert!(ChooseCommand::parse("A,,B").is_err());
+        assert!(ChooseCommand::parse("A,B,").is_err());
+        assert!(ChooseCommand::parse("A*0,B").is_err());
+        assert!(ChooseCommand::parse("\"A,B").is_err());
+        assert!(ChooseCommand::parse("A\"B\",C").is_err());
+    }
+
+    #[test]
+    fn draw_without_replacement() {
+        let choices = ChooseCommand::parse("A,B*5,C,D").unwrap();
+        let mut dice = DiceSource::seeded(42);
+
+        for count in 1..=4 {
+            let mut drawn = ChooseCommand::draw(&choices, count, &mut dice);
+            assert_eq!(drawn.len(), count);
+            drawn.sort();
+            drawn.dedup();
+            assert_eq!(drawn.len(), count);
+        }
+
+        // The heavier one comes first more often.
+        let first_b = (0..1000)
+            .filter(|_| ChooseCommand::draw(&choices, 4, &mut dice)[0] == 1)
+            .count();
+        assert!(first_b > 500);
+    }
+}
@@ -0,0 +1,464 @@
+use anyhow::Result;
+use serenity::builder::{
+    CreateActionRow, CreateButton, CreateCommand, CreateCommandOption, CreateEmbed,
+    CreateInteractionResponse, CreateInteractionResponseMessage, EditMessage,
+};
+use serenity::model::application::{
+    ButtonStyle, CommandInteraction, CommandOptionType, ComponentInteraction,
+};
+use serenity::prelude::Context;
+
+use crate::combat::{Combat, Participant, FIREARM_BONUS};
+use crate::commands::create_sheet::MAX_NAME_LENGTH;
+use crate::commands::{BotCommand, BotComponent, CommandStatus, InteractionUtil, SendEmbed};
+use crate::storage::combat::CombatTracker;
+use crate::storage::Storage;
+
+/// The maximum number of participants of a combat.
+const MAX_PARTICIPANTS: usize = 30;
+
+/// A command that tracks the initiative order and the rounds of a combat.
+pub struct CombatCommand;
+
+/// Buttons on the tracker to advance the combat.
+pub struct CombatComponent;
+
+impl CombatCommand {
+    /// Builds the tracker of the combat.
+    fn tracker(combat: &Combat) -> (CreateEmbed, Vec<CreateActionRow>) {
+        let embed = CreateEmbed::new()
+            .title(format!(":crossed_swords: Combat - Round {}", combat.round))
+            .description(CombatCommand::order_text(combat));
+
+        let buttons = vec![
+            CreateButton::new(CombatComponent.custom_id(&["next".to_string()]))
+                .label("Next turn")
+                .emoji('⏭')
+                .style(ButtonStyle::Primary),
+            CreateButton::new(CombatComponent.custom_id(&["delay".to_string()]))
+                .label("Delay")
+                .emoji('⏳')
+                .style(ButtonStyle::Secondary),
+            CreateButton::new(CombatComponent.custom_id(&["round".to_string()]))
+                .label("Next round")
+                .emoji('🔄')
+                .style(ButtonStyle::Secondary),
+        ];
+
+        (embed, vec![CreateActionRow::Buttons(buttons)])
+    }
+
+    /// Lists the participants in the order they act.
+    fn order_text(combat: &Combat) -> String {
+        if combat.order.is_empty() {
+            return "Nobody is in the combat yet. Add participants with `/combat add`.".to_string();
+        }
+
+        let current = combat.current();
+        combat
+            .order
+            .iter()
+            .enumerate()
+            .map(|(index, participant)| {
+                let initiative = if participant.firearm {
+                    format!(
+                        "DEX {} + {} = {}",
+                        participant.dex,
+                        FIREARM_BONUS,
+                        participant.initiative()
+                    )
+                } else {
+                    format!("DEX {}", participant.dex)
+                };
+
+                if current == Some(index) {
+                    format!(":arrow_forward: **{}** ({})", participant.name, initiative)
+                } else if combat.loses_turn(participant) {
+                    format!(":dizzy_face: ~~{}~~ (surprised)", participant.name)
+                } else if participant.acted {
+                    format!(
+                        ":white_check_mark: ~~{}~~ ({})",
+                        participant.name, initiative
+                    )
+                } else if participant.delayed {
+                    format!(":hourglass: {} (delaying)", participant.name)
+                } else {
+                    format!(":black_small_square: {} ({})", participant.name, initiative)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Changes the combat in the channel and edits the tracker in place.
+    /// Only the keeper who started the combat can change it.
+    async fn update(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        f: impl FnOnce(&mut Combat) -> std::result::Result<String, String>,
+    ) -> Result<CommandStatus> {
+        let channel_id = interaction.channel_id;
+        let tracker =
+            Storage::with(|connection| CombatTracker::find(connection, channel_id)).await?;
+        let mut tracker = match tracker {
+            Some(tracker) => tracker,
+            None => {
+                return Ok(CommandStatus::Err(
+                    "No combat is running in this channel. Start one with /combat start."
+                        .to_string(),
+                ))
+            }
+        };
+        if tracker.owner_id != interaction.user.id {
+            return Ok(CommandStatus::Err(
+                "Only the keeper who started the combat can change it.".to_string(),
+            ));
+        }
+
+        let message = match f(&mut tracker.combat) {
+            Ok(message) => message,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+
+        Storage::with(|connection| tracker.save(connection)).await?;
+
+        let (embed, components) = CombatCommand::tracker(&tracker.combat);
+        channel_id
+            .edit_message(
+                &ctx,
+                tracker.message_id,
+                EditMessage::new().embed(embed).components(components),
+            )
+            .await?;
+
+        // The tracker shows the change, so the others do not need to see the reply.
+        interaction
+            .create_response(
+                &ctx,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::default()
+                        .content(message)
+                        .ephemeral(true),
+                ),
+            )
+            .await?;
+
+        Ok(CommandStatus::Ok)
+    }
+
+    async fn execute_start(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let channel_id = interaction.channel_id;
+        let running =
+            Storage::with(|connection| CombatTracker::find(connection, channel_id)).await?;
+        if running.is_some() {
+            return Ok(CommandStatus::Err(
+                "A combat is already running in this channel. End it with /combat end.".to_string(),
+            ));
+        }
+
+        let combat = Combat::default();
+        let (embed, components) = CombatCommand::tracker(&combat);
+        interaction
+            .send_embed_with_components(ctx, embed, components)
+            .await?;
+
+        let message = interaction.get_response(&ctx).await?;
+        let tracker = CombatTracker {
+            channel_id,
+            owner_id: interaction.user.id,
+            message_id: message.id,
+            combat,
+        };
+        Storage::with(|connection| tracker.save(connection)).await?;
+
+        Ok(CommandStatus::Ok)
+    }
+
+    async fn execute_add(ctx: &Context, interaction: &CommandInteraction) -> Result<CommandStatus> {
+        let name = interaction
+            .get_string_option("name".into())
+            .unwrap()
+            .trim()
+            .to_string();
+        let dex = interaction.get_int_option("dex".into()).unwrap();
+        let firearm = interaction.get_bool_option("firearm".into()) == Some(true);
+        let surprised = interaction.get_bool_option("surprised".into()) == Some(true);
+
+        CombatCommand::update(ctx, interaction, |combat| {
+            if combat.order.len() >= MAX_PARTICIPANTS {
+                return Err(format!(
+                    "A combat can have up to {} participants.",
+                    MAX_PARTICIPANTS
+                ));
+            }
+            let participant = Participant::new(&name, dex, firearm, surprised);
+            let initiative = participant.initiative();
+            combat.add(participant)?;
+            Ok(format!(
+                "{} joined the combat with initiative {}.",
+                name, initiative
+            ))
+        })
+        .await
+    }
+
+    async fn execute_remove(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let name = interaction.get_string_option("name".into()).unwrap().trim();
+
+        CombatCommand::update(ctx, interaction, |combat| {
+            let participant = combat.remove(name)?;
+            Ok(format!("{} left the combat.", participant.name))
+        })
+        .await
+    }
+
+    async fn execute_end(ctx: &Context, interaction: &CommandInteraction) -> Result<CommandStatus> {
+        let channel_id = interaction.channel_id;
+        let tracker =
+            Storage::with(|connection| CombatTracker::find(connection, channel_id)).await?;
+        let tracker = match tracker {
+            Some(tracker) => tracker,
+            None => {
+                return Ok(CommandStatus::Err(
+                    "No combat is running in this channel.".to_string(),
+                ))
+            }
+        };
+        if tracker.owner_id != interaction.user.id {
+            return Ok(CommandStatus::Err(
+                "Only the keeper who started the combat can end it.".to_string(),
+            ));
+        }
+
+        Storage::with(|connection| CombatTracker::delete(connection, channel_id)).await?;
+
+        // The tracker may have been deleted by someone.
+        let _ = channel_id
+            .edit_message(
+                &ctx,
+                tracker.message_id,
+                EditMessage::new().components(Vec::new()),
+            )
+            .await;
+
+        interaction
+            .send_embed(
+                ctx,
+                CreateEmbed::new()
+                    .title(":crossed_swords: The combat has ended")
+                    .description(format!("It lasted {} round(s).", tracker.combat.round)),
+            )
+            .await?;
+
+        Ok(CommandStatus::Ok)
+    }
+}
+
+#[naming]
+#[serenity::async_trait]
+impl BotCommand for CombatCommand {
+    fn create(&self) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description("Tracks the initiative order of a combat following the Call of Cthulhu 7th Edition.")
+            .description_localized("ja", "第7版のルールに基づいて戦闘の行動順を管理します.")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "start",
+                    "Starts a combat in this channel and posts the tracker.",
+                )
+                .description_localized("ja", "このチャンネルで戦闘を開始し, 管理表を投稿します."),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "add",
+                    "Adds a participant to the combat.",
+                )
+                .description_localized("ja", "戦闘に参加者を追加します.")
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "name",
+                        "A name of the participant",
+                    )
+                    .name_localized("ja", "名前")
+                    .description_localized("ja", "参加者の名前")
+                    .max_length(MAX_NAME_LENGTH)
+                    .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Integer, "dex", "DEX of the participant")
+                        .description_localized("ja", "参加者のDEX")
+                        .min_int_value(0)
+                        .max_int_value(999)
+                        .required(true),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Boolean,
+                        "firearm",
+                        "Whether the participant has readied a firearm (+50 to DEX)",
+                    )
+                    .name_localized("ja", "銃器")
+                    .description_localized("ja", "銃器を構えているか (DEX+50)"),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Boolean,
+                        "surprised",
+                        "Whether the participant is surprised (loses the turn of the first round)",
+                    )
+                    .name_localized("ja", "奇襲")
+                    .description_localized("ja", "奇襲を受けたか (最初のラウンドの手番を失います)"),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "remove",
+                    "Removes a participant from the combat.",
+                )
+                .description_localized("ja", "戦闘から参加者を取り除きます.")
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "name",
+                        "A name of the participant",
+                    )
+                    .name_localized("ja", "名前")
+                    .description_localized("ja", "参加者の名前")
+                    .max_length(MAX_NAME_LENGTH)
+                    .required(true),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "end",
+                    "Ends the combat in this channel.",
+                )
+                .description_localized("ja", "このチャンネルの戦闘を終了します."),
+            )
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        match interaction.get_subcommand() {
+            Some("start") => CombatCommand::execute_start(ctx, interaction).await,
+            Some("add") => CombatCommand::execute_add(ctx, interaction).await,
+            Some("remove") => CombatCommand::execute_remove(ctx, interaction).await,
+            Some("end") => CombatCommand::execute_end(ctx, interaction).await,
+            _ => Ok(CommandStatus::Err("Unknown subcommand.".to_string())),
+        }
+    }
+}
+
+#[serenity::async_trait]
+impl BotComponent for CombatComponent {
+    fn name(&self) -> &str {
+        "combat"
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &ComponentInteraction,
+        args: &[&str],
+    ) -> Result<CommandStatus> {
+        let channel_id = interaction.channel_id;
+        let tracker =
+            Storage::with(|connection| CombatTracker::find(connection, channel_id)).await?;
+        let mut tracker = match tracker {
+            Some(tracker) if tracker.message_id == interaction.message.id => tracker,
+            _ => {
+                return Ok(CommandStatus::Err(
+                    "This combat has already ended.".to_string(),
+                ))
+            }
+        };
+        if tracker.owner_id != interaction.user.id {
+            return Ok(CommandStatus::Err(
+                "Only the keeper who started the combat can advance it.".to_string(),
+            ));
+        }
+
+        let result = match args {
+            ["next"] => {
+                tracker.combat.next_turn();
+                Ok(())
+            }
+            ["round"] => {
+                tracker.combat.next_round();
+                Ok(())
+            }
+            ["delay"] => tracker.combat.delay(),
+            _ => return Ok(CommandStatus::Err("The button is broken.".to_string())),
+        };
+        if let Err(message) = result {
+            return Ok(CommandStatus::Err(message));
+        }
+
+        Storage::with(|connection| tracker.save(connection)).await?;
+
+        let (embed, components) = CombatCommand::tracker(&tracker.combat);
+        interaction
+            .create_response(
+                &ctx,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::default()
+                        .embed(embed)
+                        .components(components),
+                ),
+            )
+            .await?;
+
+        Ok(CommandStatus::Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn order_is_listed() {
+        let mut combat = Combat::default();
+        assert!(CombatCommand::order_text(&combat).contains("/combat add"));
+
+        combat
+            .add(Participant::new("Harvey", 40, true, false))
+            .unwrap();
+        combat
+            .add(Participant::new("Cultist", 60, false, false))
+            .unwrap();
+        combat
+            .add(Participant::new("Ghoul", 50, false, true))
+            .unwrap();
+        combat
+            .add(Participant::new("Dog", 70, false, false))
+            .unwrap();
+        combat.next_turn();
+        combat.delay().unwrap();
+
+        assert_eq!(
+            CombatCommand::order_text(&combat),
+            [
+                ":white_check_mark: ~~Harvey~~ (DEX 40 + 50 = 90)",
+                ":arrow_forward: **Cultist** (DEX 60)",
+                ":dizzy_face: ~~Ghoul~~ (surprised)",
+                ":hourglass: Dog (delaying)",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -1,18 +1,33 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
-use serenity::builder::{CreateCommand, CreateEmbed};
-use serenity::model::application::CommandInteraction;
+use serenity::builder::{CreateCommand, CreateCommandOption, CreateEmbed, CreateEmbedFooter};
+use serenity::model::application::{CommandInteraction, CommandOptionType};
 use serenity::prelude::Context;
-use tyche::dice::roller::FastRand;
 use tyche::expr::Describe;
 use tyche::Expr;
 
+use crate::commands::session::SessionCommand;
 use crate::commands::{BotCommand, CommandStatus, InteractionUtil, SendEmbed};
+use crate::dice::DiceSource;
+use crate::storage::character::Character;
+use crate::storage::session::RollRecord;
+use crate::storage::Storage;
+
+/// The maximum length of a name of the character.
+pub const MAX_NAME_LENGTH: u16 = 64;
+
+/// The derived values saved as the characteristics of the character:
+/// the ones tracked during the play and the ones rolled as checks (ex. IDEA).
+/// The others (ex. damage bonus, skill points) are only shown on the sheet.
+const SAVED_DERIVED: [&str; 8] = ["HP", "MP", "SAN", "Luck", "MOV", "Build", "IDEA", "KNOW"];
 
 /// A command that creates a character sheet.
 pub struct CSCommand;
 
 /// Represents a status which the character must have.
 struct Status<'l> {
+    pub emoji: &'l str,
     pub name: &'l str,
     pub roll: &'l str,
 }
@@ -20,39 +35,215 @@ struct Status<'l> {
 /// A list of the statuses required.
 const STATUSES: [Status; 8] = [
     Status {
-        name: ":dagger: STR",
+        emoji: ":dagger:",
+        name: "STR",
         roll: "3d6",
     },
     Status {
-        name: ":umbrella: CON",
+        emoji: ":umbrella:",
+        name: "CON",
         roll: "3d6",
     },
     Status {
-        name: ":heart: POW",
+        emoji: ":heart:",
+        name: "POW",
         roll: "3d6",
     },
     Status {
-        name: ":dash: DEX",
+        emoji: ":dash:",
+        name: "DEX",
         roll: "3d6",
     },
     Status {
-        name: ":star: APP",
+        emoji: ":star:",
+        name: "APP",
         roll: "3d6",
     },
     Status {
-        name: ":elephant: SIZ",
+        emoji: ":elephant:",
+        name: "SIZ",
         roll: "2d6+6",
     },
     Status {
-        name: ":bulb: INT",
+        emoji: ":bulb:",
+        name: "INT",
         roll: "2d6+6",
     },
     Status {
-        name: ":books: EDU",
+        emoji: ":books:",
+        name: "EDU",
         roll: "3d6+3",
     },
 ];
 
+/// A list of the statuses required in Call of Cthulhu 7th Edition.
+const STATUSES_7TH: [Status; 9] = [
+    Status {
+        emoji: ":dagger:",
+        name: "STR",
+        roll: "3d6*5",
+    },
+    Status {
+        emoji: ":umbrella:",
+        name: "CON",
+        roll: "3d6*5",
+    },
+    Status {
+        emoji: ":elephant:",
+        name: "SIZ",
+        roll: "(2d6+6)*5",
+    },
+    Status {
+        emoji: ":dash:",
+        name: "DEX",
+        roll: "3d6*5",
+    },
+    Status {
+        emoji: ":star:",
+        name: "APP",
+        roll: "3d6*5",
+    },
+    Status {
+        emoji: ":bulb:",
+        name: "INT",
+        roll: "(2d6+6)*5",
+    },
+    Status {
+        emoji: ":heart:",
+        name: "POW",
+        roll: "3d6*5",
+    },
+    Status {
+        emoji: ":books:",
+        name: "EDU",
+        roll: "(2d6+6)*5",
+    },
+    Status {
+        emoji: ":four_leaf_clover:",
+        name: "Luck",
+        roll: "3d6*5",
+    },
+];
+
+/// A list of the statuses required in Delta Green.
+const STATUSES_DG: [Status; 6] = [
+    Status {
+        emoji: ":dagger:",
+        name: "STR",
+        roll: "4d6kh3",
+    },
+    Status {
+        emoji: ":umbrella:",
+        name: "CON",
+        roll: "4d6kh3",
+    },
+    Status {
+        emoji: ":dash:",
+        name: "DEX",
+        roll: "4d6kh3",
+    },
+    Status {
+        emoji: ":bulb:",
+        name: "INT",
+        roll: "4d6kh3",
+    },
+    Status {
+        emoji: ":heart:",
+        name: "POW",
+        roll: "4d6kh3",
+    },
+    Status {
+        emoji: ":star:",
+        name: "CHA",
+        roll: "4d6kh3",
+    },
+];
+
+/// A list of the statuses required in the BRP 2023 rule book.
+const STATUSES_BRP: [Status; 7] = [
+    Status {
+        emoji: ":dagger:",
+        name: "STR",
+        roll: "3d6",
+    },
+    Status {
+        emoji: ":umbrella:",
+        name: "CON",
+        roll: "3d6",
+    },
+    Status {
+        emoji: ":elephant:",
+        name: "SIZ",
+        roll: "2d6+6",
+    },
+    Status {
+        emoji: ":bulb:",
+        name: "INT",
+        roll: "2d6+6",
+    },
+    Status {
+        emoji: ":heart:",
+        name: "POW",
+        roll: "3d6",
+    },
+    Status {
+        emoji: ":dash:",
+        name: "DEX",
+        roll: "3d6",
+    },
+    Status {
+        emoji: ":star:",
+        name: "CHA",
+        roll: "3d6",
+    },
+];
+
+/// Represents a value derived from the statuses.
+struct Derived {
+    pub emoji: &'static str,
+    pub name: &'static str,
+    pub value: String,
+    /// How the value is calculated.
+    pub formula: String,
+}
+
+/// Represents a way to generate a character following a game system.
+struct Profile<'l> {
+    /// An identifier of the profile used as a value of the option.
+    pub id: &'l str,
+    pub name: &'l str,
+    pub statuses: &'l [Status<'l>],
+    pub derive: fn(&HashMap<&str, i32>) -> Vec<Derived>,
+}
+
+/// A list of the profiles. The first one is used by default.
+const PROFILES: [Profile; 4] = [
+    Profile {
+        id: "6th",
+        name: "Call of Cthulhu 6th Edition",
+        statuses: &STATUSES,
+        derive: CSCommand::derive_6th,
+    },
+    Profile {
+        id: "7th",
+        name: "Call of Cthulhu 7th Edition",
+        statuses: &STATUSES_7TH,
+        derive: CSCommand::derive_7th,
+    },
+    Profile {
+        id: "dg",
+        name: "Delta Green",
+        statuses: &STATUSES_DG,
+        derive: CSCommand::derive_dg,
+    },
+    Profile {
+        id: "brp",
+        name: "BRP 2023",
+        statuses: &STATUSES_BRP,
+        derive: CSCommand::derive_brp,
+    },
+];
+
 #[naming]
 #[serenity::async_trait]
 impl BotCommand for CSCommand {
@@ -60,6 +251,31 @@ impl BotCommand for CSCommand {
         CreateCommand::new(self.name())
             .description("Creates a character sheet.")
             .description_localized("ja", "キャラクターシートを作成します.")
+            .add_option(
+                PROFILES.iter().fold(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "system",
+                        "A game system (default: Call of Cthulhu 6th Edition)",
+                    )
+                    .name_localized("ja", "システム")
+                    .description_localized("ja", "ゲームシステム (既定: クトゥルフ神話TRPG 第6版)"),
+                    |option, profile| option.add_string_choice(profile.name, profile.id),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "name",
+                    "A name to save the character as (Leave it empty not to save.)",
+                )
+                .name_localized("ja", "名前")
+                .description_localized(
+                    "ja",
+                    "キャラクターを保存する名前 (空欄にすると保存しません.)",
+                )
+                .max_length(MAX_NAME_LENGTH),
+            )
     }
 
     async fn execute(
@@ -69,21 +285,441 @@ impl BotCommand for CSCommand {
     ) -> Result<CommandStatus> {
         let author = interaction.get_nickname();
 
-        let mut roller = FastRand::default();
+        let profile = interaction
+            .get_string_option("system".into())
+            .and_then(|id| PROFILES.iter().find(|profile| profile.id == id))
+            .unwrap_or(&PROFILES[0]);
+
+        let mut values = HashMap::new();
+        let mut records = Vec::new();
 
         let embed = CreateEmbed::new().title(format!("{}'s character", author));
-        let embed = STATUSES.iter().fold(embed, |embed, status| {
-            let expr: Expr = status.roll.parse().unwrap();
-            let result = expr.eval(&mut roller).unwrap();
+        let embed = DiceSource::with_guild(interaction.guild_id, |dice| {
+            profile.statuses.iter().fold(embed, |embed, status| {
+                let expr: Expr = status.roll.parse().unwrap();
+                let result = expr.eval(dice).unwrap();
+                let value = result.calc().unwrap();
+                values.insert(status.name, value);
+                records.push(RollRecord {
+                    roller: author.clone(),
+                    expression: format!("{} ({})", status.name, status.roll),
+                    dice: result.describe(None),
+                    result: value.to_string(),
+                    tier: None,
+                });
+                embed.field(
+                    format!("{} {} {}", status.emoji, status.name, value),
+                    result.describe(None),
+                    true,
+                )
+            })
+        })
+        .await?;
+
+        let derived = (profile.derive)(&values);
+
+        let mut embed = derived.iter().fold(embed, |embed, derived| {
             embed.field(
-                format!("{} {}", status.name, result.calc().unwrap()),
-                result.describe(None),
+                format!("{} {} {}", derived.emoji, derived.name, derived.value),
+                &derived.formula,
                 true,
             )
         });
 
+        if let Some(name) = interaction.get_string_option("name".into()) {
+            let characteristics = CSCommand::saved_values(profile, &values, &derived);
+
+            let owner_id = interaction.user.id;
+            let character = Storage::with(|connection| {
+                Character::insert(
+                    connection,
+                    owner_id,
+                    name,
+                    profile.id,
+                    &characteristics,
+                    &[],
+                )
+            })
+            .await?;
+            if character.is_none() {
+                return Ok(CommandStatus::Err(format!(
+                    "You already have a character named \"{}\".",
+                    name
+                )));
+            }
+
+            embed = embed
+                .title(format!("{}'s character \"{}\"", author, name))
+                .footer(CreateEmbedFooter::new("Saved. Edit it with /char set."));
+        }
+
+        SessionCommand::record_command(interaction, &records).await?;
         interaction.send_embed(ctx, embed).await?;
 
         Ok(CommandStatus::Ok)
     }
 }
+
+impl CSCommand {
+    /// Gets a name of the game system from the identifier of the profile.
+    pub fn system_name(id: &str) -> Option<&'static str> {
+        PROFILES
+            .iter()
+            .find(|profile| profile.id == id)
+            .map(|profile| profile.name)
+    }
+
+    /// Lists the values saved as the characteristics: the statuses and the derived ones allowed.
+    fn saved_values<'l>(
+        profile: &Profile<'l>,
+        values: &HashMap<&str, i32>,
+        derived: &'l [Derived],
+    ) -> Vec<(&'l str, i32)> {
+        profile
+            .statuses
+            .iter()
+            .map(|status| (status.name, values[status.name]))
+            .chain(derived.iter().filter_map(|derived| {
+                if !SAVED_DERIVED
+                    .iter()
+                    .any(|name| name.eq_ignore_ascii_case(derived.name))
+                {
+                    return None;
+                }
+                derived
+                    .value
+                    .parse()
+                    .ok()
+                    .map(|value| (derived.name, value))
+            }))
+            .collect()
+    }
+
+    /// Calculates the derived values following Call of Cthulhu 6th Edition.
+    fn derive_6th(values: &HashMap<&str, i32>) -> Vec<Derived> {
+        let str = values["STR"];
+        let con = values["CON"];
+        let pow = values["POW"];
+        let siz = values["SIZ"];
+        let int = values["INT"];
+        let edu = values["EDU"];
+
+        vec![
+            Derived {
+                emoji: ":brain:",
+                name: "SAN",
+                value: (pow * 5).to_string(),
+                formula: format!("POW {} * 5", pow),
+            },
+            Derived {
+                emoji: ":thought_balloon:",
+                name: "IDEA",
+                value: (int * 5).to_string(),
+                formula: format!("INT {} * 5", int),
+            },
+            Derived {
+                emoji: ":four_leaf_clover:",
+                name: "LUCK",
+                value: (pow * 5).to_string(),
+                formula: format!("POW {} * 5", pow),
+            },
+            Derived {
+                emoji: ":mortar_board:",
+                name: "KNOW",
+                value: (edu * 5).to_string(),
+                formula: format!("EDU {} * 5", edu),
+            },
+            Derived {
+                emoji: ":hearts:",
+                name: "HP",
+                value: ((con + siz + 1) / 2).to_string(),
+                formula: format!("(CON {} + SIZ {}) / 2, rounded up", con, siz),
+            },
+            Derived {
+                emoji: ":sparkles:",
+                name: "MP",
+                value: pow.to_string(),
+                formula: format!("POW {}", pow),
+            },
+            Derived {
+                emoji: ":punch:",
+                name: "DB",
+                value: CSCommand::damage_bonus_6th(str + siz),
+                formula: format!("STR {} + SIZ {} = {}", str, siz, str + siz),
+            },
+            Derived {
+                emoji: ":briefcase:",
+                name: "Occupation",
+                value: (edu * 20).to_string(),
+                formula: format!("EDU {} * 20", edu),
+            },
+            Derived {
+                emoji: ":art:",
+                name: "Hobby",
+                value: (int * 10).to_string(),
+                formula: format!("INT {} * 10", int),
+            },
+        ]
+    }
+
+    /// Calculates the derived values following Delta Green.
+    fn derive_dg(values: &HashMap<&str, i32>) -> Vec<Derived> {
+        let str = values["STR"];
+        let con = values["CON"];
+        let pow = values["POW"];
+
+        vec![
+            Derived {
+                emoji: ":hearts:",
+                name: "HP",
+                value: ((str + con + 1) / 2).to_string(),
+                formula: format!("(STR {} + CON {}) / 2, rounded up", str, con),
+            },
+            Derived {
+                emoji: ":sparkles:",
+                name: "WP",
+                value: pow.to_string(),
+                formula: format!("POW {}", pow),
+            },
+            Derived {
+                emoji: ":brain:",
+                name: "SAN",
+                value: (pow * 5).to_string(),
+                formula: format!("POW {} * 5", pow),
+            },
+            Derived {
+                emoji: ":broken_heart:",
+                name: "BP",
+                value: (pow * 5 - pow).to_string(),
+                formula: format!("SAN {} - POW {}", pow * 5, pow),
+            },
+        ]
+    }
+
+    /// Calculates the derived values following the BRP 2023 rule book.
+    fn derive_brp(values: &HashMap<&str, i32>) -> Vec<Derived> {
+        let str = values["STR"];
+        let con = values["CON"];
+        let siz = values["SIZ"];
+        let int = values["INT"];
+        let pow = values["POW"];
+        let dex = values["DEX"];
+        let cha = values["CHA"];
+
+        let mut derived = vec![
+            Derived {
+                emoji: ":hearts:",
+                name: "HP",
+                value: ((con + siz + 1) / 2).to_string(),
+                formula: format!("(CON {} + SIZ {}) / 2, rounded up", con, siz),
+            },
+            Derived {
+                emoji: ":sparkles:",
+                name: "MP",
+                value: pow.to_string(),
+                formula: format!("POW {}", pow),
+            },
+            Derived {
+                emoji: ":punch:",
+                name: "DM",
+                // BRP shares the table with Call of Cthulhu 6th Edition.
+                value: CSCommand::damage_bonus_6th(str + siz),
+                formula: format!("STR {} + SIZ {} = {}", str, siz, str + siz),
+            },
+            Derived {
+                emoji: ":athletic_shoe:",
+                name: "MOV",
+                value: "10".to_string(),
+                formula: "Human".to_string(),
+            },
+        ];
+
+        // Characteristic rolls.
+        let rolls = [
+            (":muscle:", "Effort", "STR", str),
+            (":runner:", "Stamina", "CON", con),
+            (":thought_balloon:", "Idea", "INT", int),
+            (":four_leaf_clover:", "Luck", "POW", pow),
+            (":cartwheel:", "Agility", "DEX", dex),
+            (":speech_balloon:", "Charisma", "CHA", cha),
+        ];
+        derived.extend(rolls.iter().map(|(emoji, name, status, value)| Derived {
+            emoji,
+            name,
+            value: (value * 5).to_string(),
+            formula: format!("{} {} * 5", status, value),
+        }));
+
+        derived
+    }
+
+    /// Looks up the damage bonus from STR + SIZ following Call of Cthulhu 6th Edition.
+    fn damage_bonus_6th(total: i32) -> String {
+        match total {
+            ..=12 => "-1d6".to_string(),
+            13..=16 => "-1d4".to_string(),
+            17..=24 => "0".to_string(),
+            25..=32 => "+1d4".to_string(),
+            33..=40 => "+1d6".to_string(),
+            // Each 16 points above 40 adds 1d6.
+            _ => format!("+{}d6", (total - 41) / 16 + 2),
+        }
+    }
+
+    /// Calculates the derived values following Call of Cthulhu 7th Edition.
+    fn derive_7th(values: &HashMap<&str, i32>) -> Vec<Derived> {
+        let str = values["STR"];
+        let con = values["CON"];
+        let siz = values["SIZ"];
+        let dex = values["DEX"];
+        let pow = values["POW"];
+
+        let mov = if dex < siz && str < siz {
+            7
+        } else if dex > siz && str > siz {
+            9
+        } else {
+            8
+        };
+        let mov_formula = if mov == 7 {
+            format!("DEX {} < SIZ {} and STR {} < SIZ {}", dex, siz, str, siz)
+        } else if mov == 9 {
+            format!("DEX {} > SIZ {} and STR {} > SIZ {}", dex, siz, str, siz)
+        } else {
+            format!("DEX {}, STR {} and SIZ {}", dex, str, siz)
+        };
+
+        let (damage_bonus, build) = CSCommand::damage_bonus_7th(str + siz);
+
+        vec![
+            Derived {
+                emoji: ":hearts:",
+                name: "HP",
+                value: ((con + siz) / 10).to_string(),
+                formula: format!("(CON {} + SIZ {}) / 10", con, siz),
+            },
+            Derived {
+                emoji: ":sparkles:",
+                name: "MP",
+                value: (pow / 5).to_string(),
+                formula: format!("POW {} / 5", pow),
+            },
+            Derived {
+                emoji: ":brain:",
+                name: "SAN",
+                value: pow.to_string(),
+                formula: format!("POW {}", pow),
+            },
+            Derived {
+                emoji: ":athletic_shoe:",
+                name: "MOV",
+                value: mov.to_string(),
+                formula: mov_formula,
+            },
+            Derived {
+                emoji: ":punch:",
+                name: "DB",
+                value: damage_bonus,
+                formula: format!("STR {} + SIZ {} = {}", str, siz, str + siz),
+            },
+            Derived {
+                emoji: ":muscle:",
+                name: "Build",
+                value: build.to_string(),
+                formula: format!("STR {} + SIZ {} = {}", str, siz, str + siz),
+            },
+        ]
+    }
+
+    /// Looks up the damage bonus and the build from STR + SIZ following Call of Cthulhu 7th Edition.
+    fn damage_bonus_7th(total: i32) -> (String, i32) {
+        match total {
+            ..=64 => ("-2".to_string(), -2),
+            65..=84 => ("-1".to_string(), -1),
+            85..=124 => ("0".to_string(), 0),
+            125..=164 => ("+1d4".to_string(), 1),
+            165..=204 => ("+1d6".to_string(), 2),
+            // Each 80 points above 204 adds 1d6 and 1 build.
+            _ => {
+                let extra = (total - 205) / 80 + 2;
+                (format!("+{}d6", extra), extra + 1)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tyche::dice::roller::Max;
+
+    use super::*;
+
+    #[test]
+    fn profiles_are_consistent() {
+        for profile in PROFILES.iter() {
+            let values = profile
+                .statuses
+                .iter()
+                .map(|status| {
+                    let expr: Expr = status.roll.parse().unwrap();
+                    (status.name, expr.eval(&mut Max).unwrap().calc().unwrap())
+                })
+                .collect::<HashMap<_, _>>();
+
+            // Every status used in the derived values must be rolled.
+            let derived = (profile.derive)(&values);
+            assert!(!derived.is_empty(), "{}", profile.id);
+
+            // An embed can hold 25 fields at most.
+            assert!(
+                profile.statuses.len() + derived.len() <= 25,
+                "{}",
+                profile.id
+            );
+        }
+    }
+
+    #[test]
+    fn only_rollable_values_are_saved() {
+        let saved = |id: &str| {
+            let profile = PROFILES.iter().find(|profile| profile.id == id).unwrap();
+            let values = profile
+                .statuses
+                .iter()
+                .map(|status| (status.name, 10))
+                .collect::<HashMap<_, _>>();
+            let derived = (profile.derive)(&values);
+            CSCommand::saved_values(profile, &values, &derived)
+                .iter()
+                .map(|(name, _)| name.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            saved("6th"),
+            vec![
+                "STR", "CON", "POW", "DEX", "APP", "SIZ", "INT", "EDU", "SAN", "IDEA", "LUCK",
+                "KNOW", "HP", "MP"
+            ]
+        );
+        assert_eq!(saved("7th")[9..], ["HP", "MP", "SAN", "MOV", "Build"]);
+        assert!(!saved("brp").contains(&"DM".to_string()));
+    }
+
+    #[test]
+    fn damage_bonus_boundaries() {
+        assert_eq!(CSCommand::damage_bonus_6th(12), "-1d6");
+        assert_eq!(CSCommand::damage_bonus_6th(13), "-1d4");
+        assert_eq!(CSCommand::damage_bonus_6th(24), "0");
+        assert_eq!(CSCommand::damage_bonus_6th(25), "+1d4");
+        assert_eq!(CSCommand::damage_bonus_6th(40), "+1d6");
+        assert_eq!(CSCommand::damage_bonus_6th(41), "+2d6");
+        assert_eq!(CSCommand::damage_bonus_6th(57), "+3d6");
+
+        assert_eq!(CSCommand::damage_bonus_7th(64), ("-2".to_string(), -2));
+        assert_eq!(CSCommand::damage_bonus_7th(85), ("0".to_string(), 0));
+        assert_eq!(CSCommand::damage_bonus_7th(204), ("+1d6".to_string(), 2));
+        assert_eq!(CSCommand::damage_bonus_7th(205), ("+2d6".to_string(), 3));
+        assert_eq!(CSCommand::damage_bonus_7th(285), ("+3d6".to_string(), 4));
+    }
+}
@@ -0,0 +1,253 @@
+use anyhow::Result;
+use serenity::builder::{CreateCommand, CreateCommandOption, CreateEmbed};
+use serenity::model::application::{CommandInteraction, CommandOptionType};
+use serenity::prelude::Context;
+
+use crate::commands::character::MAX_VALUE;
+use crate::commands::roll::RollCommand;
+use crate::commands::session::SessionCommand;
+use crate::commands::{BotCommand, CommandStatus, InteractionUtil, SendEmbed};
+use crate::dice::DiceSource;
+use crate::rules;
+use crate::storage::session::RollRecord;
+
+/// A maximum number of skills that can be improved at once.
+const MAX_SKILL_NUM: usize = 20;
+
+/// A skill value which grants a SAN bonus when reached.
+const SAN_BONUS_SKILL: i32 = 90;
+
+/// A command that does improvement checks following Call of Cthulhu 7th Edition.
+pub struct ImproveCommand;
+
+#[naming]
+#[serenity::async_trait]
+impl BotCommand for ImproveCommand {
+    fn create(&self) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description(
+                "Does improvement checks for skills following the Call of Cthulhu 7th Edition.",
+            )
+            .description_localized("ja", "第7版のルールに基づいて技能の成長ロールを行います.")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "skills",
+                    "Comma-separated skills with values (ex. Spot Hidden 60, Library Use 45)",
+                )
+                .name_localized("ja", "技能")
+                .description_localized(
+                    "ja",
+                    "カンマ区切りの技能名と技能値 (例: 目星 60, 図書館 45)",
+                )
+                .required(true),
+            )
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let skills = interaction.get_string_option("skills".into()).unwrap();
+
+        let skills = match ImproveCommand::parse_skills(skills) {
+            Ok(skills) => skills,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+        if skills.is_empty() || skills.len() > MAX_SKILL_NUM {
+            return Ok(CommandStatus::Err(format!(
+                "A number of skills must be 1-{}.",
+                MAX_SKILL_NUM
+            )));
+        }
+
+        let roller = interaction.get_nickname();
+        let improved = DiceSource::with_guild(interaction.guild_id, |dice| {
+            ImproveCommand::improve(&roller, skills, dice)
+        })
+        .await?;
+        let (embed, records) = match improved {
+            Ok(improved) => improved,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+
+        SessionCommand::record_command(interaction, &records).await?;
+        interaction.send_embed(ctx, embed).await?;
+
+        Ok(CommandStatus::Ok)
+    }
+}
+
+impl ImproveCommand {
+    /// Does improvement checks for the skills and builds the result with the records of the rolls.
+    fn improve(
+        roller: &str,
+        skills: Vec<(&str, i32)>,
+        dice: &mut DiceSource,
+    ) -> Result<(CreateEmbed, Vec<RollRecord>), String> {
+        let mut embed = CreateEmbed::new().title(format!("{}'s skill improvement", roller));
+        let mut records = Vec::new();
+        let mut reached = Vec::new();
+
+        for (name, value) in skills {
+            let (rolled, _) = rules::roll_percentile(0, dice);
+            let mut record = RollRecord {
+                roller: roller.to_string(),
+                expression: format!("Improvement of {} ({})", name, value),
+                dice: rolled.to_string(),
+                result: value.to_string(),
+                tier: None,
+            };
+
+            // The skill improves when the roll is over the skill or 96 or more.
+            if rolled > value || rolled >= 96 {
+                let (gained, description) = RollCommand::evaluate_dice_expr("1d10", dice)?;
+                let new_value = value + gained;
+
+                if value < SAN_BONUS_SKILL && new_value >= SAN_BONUS_SKILL {
+                    reached.push(name);
+                }
+
+                record.dice = format!("{}, +{}", rolled, description);
+                record.result = new_value.to_string();
+
+                embed = embed.field(
+                    format!(
+                        ":chart_with_upwards_trend: {} {} -> {}",
+                        name, value, new_value
+                    ),
+                    if rolled > value {
+                        format!("{} > {}, +{}", rolled, value, description)
+                    } else {
+                        format!("{} >= 96, +{}", rolled, description)
+                    },
+                    false,
+                );
+            } else {
+                embed = embed.field(
+                    format!(":heavy_minus_sign: {} {}", name, value),
+                    format!("{} <= {}", rolled, value),
+                    false,
+                );
+            }
+            records.push(record);
+        }
+
+        if !reached.is_empty() {
+            let bonus = reached
+                .iter()
+                .map(|_| RollCommand::evaluate_dice_expr("2d6", dice))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            embed = embed.field(
+                format!(
+                    ":brain: SAN +{}",
+                    bonus.iter().map(|(gained, _)| gained).sum::<i32>()
+                ),
+                reached
+                    .iter()
+                    .zip(bonus.iter())
+                    .map(|(name, (_, description))| format!("{}: {}", name, description))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                false,
+            );
+        }
+
+        Ok((embed, records))
+    }
+
+    /// Parses comma-separated skills like `Spot Hidden 60, Library Use 45`.
+    fn parse_skills(text: &str) -> Result<Vec<(&str, i32)>, String> {
+        text.split([',', '、', '，'])
+            .map(|skill| skill.trim())
+            .filter(|skill| !skill.is_empty())
+            .map(|skill| {
+                let (name, value) = skill
+                    .rsplit_once(char::is_whitespace)
+                    .ok_or_else(|| format!("No value is given to \"{}\".", skill))?;
+                let value = value
+                    .parse()
+                    .ok()
+                    .filter(|value| (0..=MAX_VALUE as i32).contains(value))
+                    .ok_or_else(|| format!("\"{}\" is not a valid skill value.", value))?;
+                Ok((name.trim(), value))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Finds a seed whose first percentile roll satisfies `f`, and returns it with the roll.
+    fn seed_rolling(f: impl Fn(i32) -> bool) -> (u64, i32) {
+        (0..)
+            .map(|seed| {
+                (
+                    seed,
+                    rules::roll_percentile(0, &mut DiceSource::seeded(seed)).0,
+                )
+            })
+            .find(|(_, rolled)| f(*rolled))
+            .unwrap()
+    }
+
+    fn improve(seed: u64, skills: Vec<(&str, i32)>) -> (String, Vec<RollRecord>) {
+        let (embed, records) =
+            ImproveCommand::improve("Harvey", skills, &mut DiceSource::seeded(seed)).unwrap();
+        (serde_json::to_string(&embed).unwrap(), records)
+    }
+
+    #[test]
+    fn parse_skills() {
+        assert_eq!(
+            ImproveCommand::parse_skills("Spot Hidden 60, 図書館 45、 Listen 0").unwrap(),
+            vec![("Spot Hidden", 60), ("図書館", 45), ("Listen", 0)]
+        );
+        assert_eq!(ImproveCommand::parse_skills(" , ").unwrap(), vec![]);
+
+        assert!(ImproveCommand::parse_skills("Spot Hidden").is_err());
+        assert!(ImproveCommand::parse_skills("Spot Hidden sixty").is_err());
+        assert!(ImproveCommand::parse_skills("Spot Hidden -1").is_err());
+        assert!(ImproveCommand::parse_skills("Spot Hidden 1000").is_err());
+    }
+
+    #[test]
+    fn improve_when_rolled_over_the_value() {
+        let (seed, rolled) = seed_rolling(|rolled| (2..96).contains(&rolled));
+
+        let (_, records) = improve(seed, vec![("Spot Hidden", rolled - 1)]);
+        let gained = records[0].result.parse::<i32>().unwrap() - (rolled - 1);
+        assert!((1..=10).contains(&gained));
+
+        // Rolling the value itself is not over it.
+        let (_, records) = improve(seed, vec![("Spot Hidden", rolled)]);
+        assert_eq!(records[0].result, rolled.to_string());
+    }
+
+    #[test]
+    fn improve_on_96_or_more() {
+        let (seed, rolled) = seed_rolling(|rolled| rolled >= 96);
+
+        let (embed, records) = improve(seed, vec![("Spot Hidden", 99)]);
+        assert!(records[0].result.parse::<i32>().unwrap() > 99);
+        assert!(embed.contains(&format!("{} >= 96", rolled)));
+    }
+
+    #[test]
+    fn san_bonus_when_reaching_90() {
+        let (seed, _) = seed_rolling(|rolled| rolled > 89);
+
+        let (embed, records) = improve(seed, vec![("Spot Hidden", 89)]);
+        assert!(records[0].result.parse::<i32>().unwrap() >= SAN_BONUS_SKILL);
+        assert!(embed.contains(":brain: SAN +"));
+        assert!(embed.contains("Spot Hidden: "));
+
+        // No bonus for the skill already 90 or more.
+        let (embed, _) = improve(seed, vec![("Spot Hidden", 90)]);
+        assert!(!embed.contains(":brain: SAN +"));
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::Result;
+use serenity::builder::{CreateCommand, CreateCommandOption, CreateEmbed};
+use serenity::model::application::{CommandInteraction, CommandOptionType};
+use serenity::prelude::Context;
+
+use crate::commands::roll::RollCommand;
+use crate::commands::session::SessionCommand;
+use crate::commands::{BotCommand, CommandStatus, InteractionUtil, SendEmbed};
+use crate::dice::DiceSource;
+use crate::madness::{Language, MadnessTable, MadnessTables};
+use crate::storage::session::RollRecord;
+
+/// A command that rolls on the tables of madness.
+pub struct MadnessCommand;
+
+impl MadnessCommand {
+    /// Gets the table and its title for the mode.
+    fn table<'t>(
+        tables: &'t MadnessTables,
+        mode: &str,
+    ) -> (&'t MadnessTable, &'static str, &'static str) {
+        match mode {
+            "summary" => (
+                &tables.summary,
+                "a bout of madness (summary)",
+                "狂気の発作 (略式)",
+            ),
+            "phobia" => (&tables.phobia, "a phobia", "恐怖症"),
+            "mania" => (&tables.mania, "a mania", "マニア"),
+            _ => (
+                &tables.realtime,
+                "a bout of madness (real time)",
+                "狂気の発作 (リアルタイム)",
+            ),
+        }
+    }
+}
+
+#[naming]
+#[serenity::async_trait]
+impl BotCommand for MadnessCommand {
+    fn create(&self) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description("Rolls on the tables of madness.")
+            .description_localized("ja", "狂気の表を振ります.")
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::String, "mode", "A table to roll on")
+                    .name_localized("ja", "表")
+                    .description_localized("ja", "振る表")
+                    .add_string_choice_localized(
+                        "Bout of madness (real time)",
+                        "realtime",
+                        [("ja", "狂気の発作 (リアルタイム)")],
+                    )
+                    .add_string_choice_localized(
+                        "Bout of madness (summary)",
+                        "summary",
+                        [("ja", "狂気の発作 (略式)")],
+                    )
+                    .add_string_choice_localized("Phobia", "phobia", [("ja", "恐怖症")])
+                    .add_string_choice_localized("Mania", "mania", [("ja", "マニア")])
+                    .required(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "language",
+                    "A language of the entry (default: the one of your Discord)",
+                )
+                .name_localized("ja", "言語")
+                .description_localized("ja", "表の言語 (既定: Discordの言語)")
+                .add_string_choice("English", "en")
+                .add_string_choice("日本語", "ja"),
+            )
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let mode = interaction.get_string_option("mode".into()).unwrap();
+        let language = Language::from_locale(
+            interaction
+                .get_string_option("language".into())
+                .unwrap_or(&interaction.locale),
+        );
+
+        let (table, title, title_ja) = MadnessCommand::table(MadnessTables::get(), mode);
+
+        let evaluated = DiceSource::with_guild(interaction.guild_id, |dice| {
+            Ok::<_, String>((
+                RollCommand::evaluate_dice_expr(&table.dice, dice)?,
+                RollCommand::evaluate_dice_expr(&table.duration, dice)?,
+            ))
+        })
+        .await?;
+        let ((rolled, dice), (duration, duration_dice)) = match evaluated {
+            Ok(evaluated) => evaluated,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+
+        let entry = match table.entry(rolled) {
+            Some(entry) => entry.get(language),
+            None => {
+                return Ok(CommandStatus::Err(format!(
+                    "The table has no entry for {}.",
+                    rolled
+                )))
+            }
+        };
+        let unit = table.unit.get(language);
+
+        let title = match language {
+            Language::En => format!("{} rolls {}", interaction.get_nickname(), title),
+            Language::Ja => format!("{} の{}", interaction.get_nickname(), title_ja),
+        };
+        let embed = CreateEmbed::new()
+            .title(title)
+            .field(format!(":game_die: {}", rolled), entry, false)
+            .field(
+                format!(":hourglass: {} {}", duration, unit),
+                &duration_dice,
+                false,
+            );
+
+        let record = RollRecord {
+            roller: interaction.get_nickname(),
+            expression: format!("{} ({})", mode, table.dice),
+            dice: format!("{} / {}", dice, duration_dice),
+            result: format!("{} ({} {})", entry, duration, unit),
+            tier: None,
+        };
+        SessionCommand::record_command(interaction, &[record]).await?;
+
+        interaction.send_embed(ctx, embed).await?;
+
+        Ok(CommandStatus::Ok)
+    }
+}
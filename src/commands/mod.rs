@@ -2,18 +2,42 @@ use anyhow::Result;
 use log::{error, info};
 use once_cell::sync::Lazy;
 use serenity::builder::{
+    AutocompleteChoice, CreateActionRow, CreateAttachment, CreateAutocompleteResponse,
     CreateCommand, CreateEmbed, CreateInteractionResponse, CreateInteractionResponseMessage,
 };
-use serenity::model::application::{Command, CommandInteraction};
+use serenity::model::application::{
+    Command, CommandDataOption, CommandDataOptionValue, CommandInteraction, ComponentInteraction,
+};
+use serenity::model::channel::Attachment;
 use serenity::model::colour::Colour;
+use serenity::model::user::User;
 use serenity::prelude::Context;
 
+use crate::commands::character::CharCommand;
 use crate::commands::choose::ChooseCommand;
+use crate::commands::combat::{CombatCommand, CombatComponent};
 use crate::commands::create_sheet::CSCommand;
-use crate::commands::opposed::{Op6Command, Op7Command};
+use crate::commands::improve::ImproveCommand;
+use crate::commands::madness::MadnessCommand;
+use crate::commands::opposed::{Op6Command, Op7Command, OpCommand};
 use crate::commands::roll::RollCommand;
-use crate::commands::skill::{Sk6Command, Sk7Command, SkBRPCommand, SkDGCommand, SkillCommand};
+use crate::commands::sanity::SanCommand;
+use crate::commands::secret::RevealComponent;
+use crate::commands::seed::SeedCommand;
+use crate::commands::session::SessionCommand;
+use crate::commands::settings::SettingsCommand;
+use crate::commands::skill::{
+    Sk6Command, Sk7Command, Sk7LuckComponent, Sk7PushComponent, SkBRPCommand, SkDGCommand,
+    SkillCommand,
+};
+use crate::commands::stats::StatsCommand;
+use crate::commands::table::TableCommand;
+use crate::commands::vitals::{HpCommand, MpCommand, SanityCommand};
 use crate::logging::BotEventCounter;
+use crate::skills::MAX_SUGGESTIONS;
+
+/// The maximum length of a choice of autocomplete.
+const MAX_CHOICE_LENGTH: usize = 100;
 
 /// Represents a handled result of the command.
 /// Note that you cannot use this for internal errors.
@@ -37,6 +61,40 @@ pub trait BotCommand {
         ctx: &Context,
         interaction: &CommandInteraction,
     ) -> Result<CommandStatus>;
+
+    /// Suggests values of the option the user is typing.
+    /// Nothing is suggested by default.
+    async fn autocomplete(
+        &self,
+        _ctx: &Context,
+        _interaction: &CommandInteraction,
+    ) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Represents a handler of message components (ex. buttons) attached to the results of commands.
+#[serenity::async_trait]
+pub trait BotComponent {
+    /// Gets a name of the component. It is used as a prefix of `custom_id`.
+    fn name(&self) -> &str;
+
+    /// Handles an interaction with the component.
+    /// `args` holds the rest of `custom_id` split by `:`.
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &ComponentInteraction,
+        args: &[&str],
+    ) -> Result<CommandStatus>;
+
+    /// Builds `custom_id` of the component from the arguments.
+    fn custom_id(&self, args: &[String]) -> String {
+        std::iter::once(self.name().to_string())
+            .chain(args.iter().cloned())
+            .collect::<Vec<_>>()
+            .join(":")
+    }
 }
 
 /// The commands which can be invoked through the bot.
@@ -47,11 +105,35 @@ static REGISTERED_COMMANDS: Lazy<Vec<Box<dyn BotCommand + Sync + Send>>> = Lazy:
         Box::new(RollCommand),
         Box::new(Op6Command),
         Box::new(Op7Command),
+        Box::new(OpCommand),
         Box::new(Sk6Command),
         Box::new(Sk7Command),
         Box::new(SkDGCommand),
         Box::new(SkBRPCommand),
         Box::new(SkillCommand),
+        Box::new(SanCommand),
+        Box::new(ImproveCommand),
+        Box::new(SeedCommand),
+        Box::new(CharCommand),
+        Box::new(SettingsCommand),
+        Box::new(SessionCommand),
+        Box::new(StatsCommand),
+        Box::new(CombatCommand),
+        Box::new(HpCommand),
+        Box::new(MpCommand),
+        Box::new(SanityCommand),
+        Box::new(MadnessCommand),
+        Box::new(TableCommand),
+    ]
+});
+
+/// The components which can be attached to the messages sent by the bot.
+static REGISTERED_COMPONENTS: Lazy<Vec<Box<dyn BotComponent + Sync + Send>>> = Lazy::new(|| {
+    vec![
+        Box::new(Sk7PushComponent),
+        Box::new(Sk7LuckComponent),
+        Box::new(RevealComponent),
+        Box::new(CombatComponent),
     ]
 });
 
@@ -104,6 +186,62 @@ impl BotCommandManager {
         Ok(())
     }
 
+    /// Answers an autocomplete request of a command.
+    pub async fn run_autocomplete(ctx: &Context, interaction: &CommandInteraction) -> Result<()> {
+        let command = REGISTERED_COMMANDS
+            .iter()
+            .find(|command| command.name() == interaction.data.name);
+        let suggestions = match command {
+            Some(command) => command.autocomplete(ctx, interaction).await?,
+            None => {
+                error!("Tried to autocomplete an unknown command.");
+                return Ok(());
+            }
+        };
+
+        // Discord rejects the choices longer than 100 characters.
+        let choices = suggestions
+            .into_iter()
+            .filter(|suggestion| suggestion.chars().count() <= MAX_CHOICE_LENGTH)
+            .take(MAX_SUGGESTIONS)
+            .map(|suggestion| AutocompleteChoice::new(suggestion.clone(), suggestion))
+            .collect();
+
+        interaction
+            .create_response(
+                &ctx,
+                CreateInteractionResponse::Autocomplete(
+                    CreateAutocompleteResponse::new().set_choices(choices),
+                ),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Handles an interaction with a component.
+    pub async fn run_component(ctx: &Context, interaction: &ComponentInteraction) -> Result<()> {
+        let custom_id = interaction.data.custom_id.split(':').collect::<Vec<_>>();
+        let (name, args) = custom_id.split_first().unwrap();
+
+        let component = REGISTERED_COMPONENTS
+            .iter()
+            .find(|component| component.name() == *name);
+        match component {
+            Some(component) => {
+                let result = component.execute(ctx, interaction, args).await?;
+
+                BotEventCounter::increment(name).await;
+
+                if let CommandStatus::Err(message) = result {
+                    Self::reply_component_error(ctx, interaction, message).await?;
+                };
+            }
+            None => error!("Tried to handle an unknown component."),
+        }
+        Ok(())
+    }
+
     /// Reports an error to the user.
     ///
     /// This method cannot be used to report an internal server error.
@@ -124,6 +262,32 @@ impl BotCommandManager {
 
         Ok(())
     }
+
+    /// Reports an error of a component to the user who touched it.
+    /// Only the user can see the message not to disturb the others.
+    async fn reply_component_error(
+        ctx: &Context,
+        interaction: &ComponentInteraction,
+        error: String,
+    ) -> Result<()> {
+        interaction
+            .create_response(
+                &ctx,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::default()
+                        .add_embed(
+                            CreateEmbed::default()
+                                .title("ERROR")
+                                .field("Message", error, false)
+                                .colour(Colour::RED),
+                        )
+                        .ephemeral(true),
+                ),
+            )
+            .await?;
+
+        Ok(())
+    }
 }
 
 /// An extension for `ApplicationCommandInteraction`.
@@ -131,11 +295,24 @@ pub trait InteractionUtil {
     /// Gets a nickname of the user who invoked the command.
     fn get_nickname(&self) -> String;
 
+    /// Gets a name of the subcommand invoked.
+    fn get_subcommand(&self) -> Option<&str>;
+
     /// Gets a value of option as `String`.
+    /// The options of the subcommand are looked up if the subcommand is invoked.
     fn get_string_option(&self, name: String) -> Option<&str>;
 
     /// Gets a value of option as `i32`.
     fn get_int_option(&self, name: String) -> Option<i32>;
+
+    /// Gets a value of option as `bool`.
+    fn get_bool_option(&self, name: String) -> Option<bool>;
+
+    /// Gets an attachment given as an option.
+    fn get_attachment_option(&self, name: String) -> Option<&Attachment>;
+
+    /// Gets a user given as an option.
+    fn get_user_option(&self, name: String) -> Option<&User>;
 }
 
 impl InteractionUtil for CommandInteraction {
@@ -146,37 +323,121 @@ impl InteractionUtil for CommandInteraction {
         }
     }
 
-    fn get_string_option(&self, name: String) -> Option<&str> {
+    fn get_subcommand(&self) -> Option<&str> {
         self.data
             .options
+            .first()
+            .filter(|option| matches!(option.value, CommandDataOptionValue::SubCommand(_)))
+            .map(|option| option.name.as_str())
+    }
+
+    fn get_string_option(&self, name: String) -> Option<&str> {
+        options_of(self)
             .iter()
             .find(|option| option.name == name)
             .map(|option| option.value.as_str().unwrap())
     }
 
     fn get_int_option(&self, name: String) -> Option<i32> {
-        self.data
-            .options
+        options_of(self)
             .iter()
             .find(|option| option.name == name)
             .map(|option| option.value.as_i64().unwrap() as i32)
     }
+
+    fn get_bool_option(&self, name: String) -> Option<bool> {
+        options_of(self)
+            .iter()
+            .find(|option| option.name == name)
+            .and_then(|option| option.value.as_bool())
+    }
+
+    fn get_attachment_option(&self, name: String) -> Option<&Attachment> {
+        options_of(self)
+            .iter()
+            .find(|option| option.name == name)
+            .and_then(|option| option.value.as_attachment_id())
+            .and_then(|id| self.data.resolved.attachments.get(&id))
+    }
+
+    fn get_user_option(&self, name: String) -> Option<&User> {
+        options_of(self)
+            .iter()
+            .find(|option| option.name == name)
+            .and_then(|option| option.value.as_user_id())
+            .and_then(|id| self.data.resolved.users.get(&id))
+    }
+}
+
+/// Gets the options of the subcommand if it is invoked, otherwise the ones of the command.
+fn options_of(interaction: &CommandInteraction) -> &[CommandDataOption] {
+    match interaction.data.options.first() {
+        Some(CommandDataOption {
+            value: CommandDataOptionValue::SubCommand(options),
+            ..
+        }) => options,
+        _ => &interaction.data.options,
+    }
 }
 
 /// An extension for `ApplicationCommandInteraction` to send an embed content easily.
 #[serenity::async_trait]
 pub trait SendEmbed<'l> {
     /// Sends an embed to the user.
-    async fn send_embed(&'l self, ctx: &Context, embed: CreateEmbed) -> Result<()>;
+    async fn send_embed(&'l self, ctx: &Context, embed: CreateEmbed) -> Result<()> {
+        self.send_embed_with_components(ctx, embed, Vec::new())
+            .await
+    }
+
+    /// Sends an embed with components (ex. buttons) to the user.
+    async fn send_embed_with_components(
+        &'l self,
+        ctx: &Context,
+        embed: CreateEmbed,
+        components: Vec<CreateActionRow>,
+    ) -> Result<()>;
+
+    /// Sends an embed with files attached to the user.
+    async fn send_embed_with_files(
+        &'l self,
+        ctx: &Context,
+        embed: CreateEmbed,
+        files: Vec<CreateAttachment>,
+    ) -> Result<()>;
 }
 
 #[serenity::async_trait]
 impl<'l> SendEmbed<'l> for CommandInteraction {
-    async fn send_embed(&'l self, ctx: &Context, embed: CreateEmbed) -> Result<()> {
+    async fn send_embed_with_components(
+        &'l self,
+        ctx: &Context,
+        embed: CreateEmbed,
+        components: Vec<CreateActionRow>,
+    ) -> Result<()> {
+        self.create_response(
+            &ctx,
+            CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::default()
+                    .add_embed(embed)
+                    .components(components),
+            ),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn send_embed_with_files(
+        &'l self,
+        ctx: &Context,
+        embed: CreateEmbed,
+        files: Vec<CreateAttachment>,
+    ) -> Result<()> {
         self.create_response(
             &ctx,
             CreateInteractionResponse::Message(
-                CreateInteractionResponseMessage::default().add_embed(embed),
+                CreateInteractionResponseMessage::default()
+                    .add_embed(embed)
+                    .add_files(files),
             ),
         )
         .await?;
@@ -184,8 +445,20 @@ impl<'l> SendEmbed<'l> for CommandInteraction {
     }
 }
 
+pub mod character;
 pub mod choose;
+pub mod combat;
 pub mod create_sheet;
+pub mod improve;
+pub mod madness;
 pub mod opposed;
 pub mod roll;
+pub mod sanity;
+pub mod secret;
+pub mod seed;
+pub mod session;
+pub mod settings;
 pub mod skill;
+pub mod stats;
+pub mod table;
+pub mod vitals;
@@ -1,4 +1,5 @@
 use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
 
 use anyhow::Result;
 use rand::Rng;
@@ -6,7 +7,13 @@ use serenity::builder::{CreateCommand, CreateCommandOption, CreateEmbed};
 use serenity::model::application::{CommandInteraction, CommandOptionType};
 use serenity::prelude::Context;
 
-use crate::commands::{BotCommand, CommandStatus, InteractionUtil, SendEmbed};
+use crate::commands::secret::RevealComponent;
+use crate::commands::settings::SettingsCommand;
+use crate::commands::skill::{SkillCommand, MAX_BONUS_DICE};
+use crate::commands::{BotCommand, CommandStatus, InteractionUtil};
+use crate::dice::DiceSource;
+use crate::rules::{self, GameSystem};
+use crate::storage::session::RollRecord;
 
 /// A command to do an opposed roll following the Call of Cthulhu 6th Edition.
 pub struct Op6Command;
@@ -14,6 +21,9 @@ pub struct Op6Command;
 /// A command to do an opposed roll following the Call of Cthulhu 7th Edition.
 pub struct Op7Command;
 
+/// A command to do an opposed roll following the game system of the server.
+pub struct OpCommand;
+
 #[naming]
 #[serenity::async_trait]
 impl BotCommand for Op6Command {
@@ -58,8 +68,18 @@ impl BotCommand for Op6Command {
                     "A comment for the roll",
                 )
                 .name_localized("ja", "コメント")
-                .description_localized("ja", "ダイスの説明"),
+                .description_localized("ja", "ダイスの説明")
+                .set_autocomplete(true),
             )
+            .add_option(RevealComponent::secret_option())
+    }
+
+    async fn autocomplete(
+        &self,
+        _ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<Vec<String>> {
+        SkillCommand::suggest(interaction).await
     }
 
     async fn execute(
@@ -70,7 +90,7 @@ impl BotCommand for Op6Command {
         let status1 = interaction.get_int_option("status1".into()).unwrap();
         let status2 = interaction.get_int_option("status2".into()).unwrap();
 
-        if status1 < 0 || status1 > 20 || status2 < 0 || status2 > 20 {
+        if !(0..=20).contains(&status1) || !(0..=20).contains(&status2) {
             return Ok(CommandStatus::Err("A status must be 0-20.".to_string()));
         }
 
@@ -88,36 +108,48 @@ impl BotCommand for Op6Command {
         let chance = 50 + (status1 - status2) * 5;
         let chance = chance.clamp(0, 100);
 
-        let result = rand::thread_rng().gen_range(1..=100);
+        let result =
+            DiceSource::with_guild(interaction.guild_id, |dice| dice.gen_range(1..=100)).await?;
         let player1_won = result <= chance;
         let player1_result_text = format!("{} ({} <= {})?", status1, result, chance);
         let player2_result_text = format!("{} ({} > {})?", status2, result, chance);
 
-        interaction
-            .send_embed(
-                ctx,
-                CreateEmbed::new()
-                    .title(comment)
-                    .field(
-                        format!(":first_place: {}", if player1_won { name1 } else { name2 }),
-                        if player1_won {
-                            &player1_result_text
-                        } else {
-                            &player2_result_text
-                        },
-                        false,
-                    )
-                    .field(
-                        format!(":second_place: {}", if player1_won { name2 } else { name1 }),
-                        if player1_won {
-                            &player2_result_text
-                        } else {
-                            &player1_result_text
-                        },
-                        false,
-                    ),
-            )
-            .await?;
+        let record = OpCommand::record(
+            interaction,
+            comment,
+            (name1, status1),
+            (name2, status2),
+            result.to_string(),
+            Some(if player1_won { name1 } else { name2 }),
+        );
+
+        RevealComponent::send_roll(
+            ctx,
+            interaction,
+            CreateEmbed::new()
+                .title(comment)
+                .field(
+                    format!(":first_place: {}", if player1_won { name1 } else { name2 }),
+                    if player1_won {
+                        &player1_result_text
+                    } else {
+                        &player2_result_text
+                    },
+                    false,
+                )
+                .field(
+                    format!(":second_place: {}", if player1_won { name2 } else { name1 }),
+                    if player1_won {
+                        &player2_result_text
+                    } else {
+                        &player1_result_text
+                    },
+                    false,
+                ),
+            Vec::new(),
+            &[record],
+        )
+        .await?;
 
         Ok(CommandStatus::Ok)
     }
@@ -162,13 +194,13 @@ impl PartialOrd for RollResult {
     }
 }
 
-impl ToString for RollResult {
-    fn to_string(&self) -> String {
+impl Display for RollResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            RollResult::ExtremeSuccess(_) => "Extreme Success".into(),
-            RollResult::HardSuccess(_) => "Hard Success".into(),
-            RollResult::Success(_) => "Success".into(),
-            RollResult::Failure(_) => "Failure".into(),
+            RollResult::ExtremeSuccess(_) => write!(f, "Extreme Success"),
+            RollResult::HardSuccess(_) => write!(f, "Hard Success"),
+            RollResult::Success(_) => write!(f, "Success"),
+            RollResult::Failure(_) => write!(f, "Failure"),
         }
     }
 }
@@ -243,8 +275,18 @@ impl BotCommand for Op7Command {
                     "A comment for the roll",
                 )
                 .name_localized("ja", "コメント")
-                .description_localized("ja", "ダイスの説明"),
+                .description_localized("ja", "ダイスの説明")
+                .set_autocomplete(true),
             )
+            .add_option(RevealComponent::secret_option())
+    }
+
+    async fn autocomplete(
+        &self,
+        _ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<Vec<String>> {
+        SkillCommand::suggest(interaction).await
     }
 
     async fn execute(
@@ -258,6 +300,14 @@ impl BotCommand for Op7Command {
         let bonus1 = interaction.get_int_option("bonus1".into()).unwrap_or(0);
         let bonus2 = interaction.get_int_option("bonus2".into()).unwrap_or(0);
 
+        let bonus_range = -MAX_BONUS_DICE..=MAX_BONUS_DICE;
+        if !bonus_range.contains(&bonus1) || !bonus_range.contains(&bonus2) {
+            return Ok(CommandStatus::Err(format!(
+                "A number of bonus or penalty dice must be {}-{}.",
+                -MAX_BONUS_DICE, MAX_BONUS_DICE
+            )));
+        }
+
         let name1 = interaction
             .get_string_option("name1".into())
             .unwrap_or("player1");
@@ -269,40 +319,8 @@ impl BotCommand for Op7Command {
             .get_string_option("comment".into())
             .unwrap_or("A competition");
 
-        fn roll_dice(status: i32, bonus: i32) -> (String, RollResult) {
-            let mut rng = rand::thread_rng();
-            let lower_digit = rng.gen_range(0..10);
-            let results = (0..(1 + bonus.abs()))
-                .map(|_| {
-                    let res = rng.gen_range(0..10) * 10 + lower_digit;
-                    if res == 0 {
-                        100
-                    } else {
-                        res
-                    }
-                })
-                .collect::<Vec<_>>();
-
-            let (selected, selected_text) = if bonus == 0 {
-                let res = results.iter().next().unwrap();
-                (*res, res.to_string())
-            } else if bonus > 0 {
-                let minimum = results.iter().min().unwrap();
-                let list = results
-                    .iter()
-                    .map(|x| x.to_string())
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                (*minimum, format!("min([{}]) = {}", list, minimum))
-            } else {
-                let maximum = results.iter().max().unwrap();
-                let list = results
-                    .iter()
-                    .map(|x| x.to_string())
-                    .collect::<Vec<_>>()
-                    .join(", ");
-                (*maximum, format!("max([{}]) = {}", list, maximum))
-            };
+        fn roll_dice(status: i32, bonus: i32, dice: &mut DiceSource) -> (String, RollResult) {
+            let (selected, selected_text) = rules::roll_percentile(bonus, dice);
 
             if selected <= status / 5 {
                 (
@@ -327,52 +345,268 @@ impl BotCommand for Op7Command {
             }
         }
 
-        let (mut result_text1, result1) = roll_dice(status1, bonus1);
-        let (mut result_text2, result2) = roll_dice(status2, bonus2);
+        let (result_text1, result_text2, player1_won) =
+            DiceSource::with_guild(interaction.guild_id, |dice| {
+                let (mut result_text1, result1) = roll_dice(status1, bonus1, dice);
+                let (mut result_text2, result2) = roll_dice(status2, bonus2, dice);
 
-        let mut player1_won = result1 > result2;
-        if result1 == result2 {
-            let mut rng = rand::thread_rng();
-            loop {
-                let player1_roll = rng.gen_range(1..=100);
-                let player2_roll = rng.gen_range(1..=100);
+                let mut player1_won = result1 > result2;
+                if result1 == result2 {
+                    loop {
+                        let player1_roll = dice.gen_range(1..=100);
+                        let player2_roll = dice.gen_range(1..=100);
 
-                result_text1 += format!(", {}", player1_roll).as_str();
-                result_text2 += format!(", {}", player2_roll).as_str();
+                        result_text1 += format!(", {}", player1_roll).as_str();
+                        result_text2 += format!(", {}", player2_roll).as_str();
 
-                if player1_roll != player2_roll {
-                    player1_won = player1_roll < player2_roll;
-                    break;
+                        if player1_roll != player2_roll {
+                            player1_won = player1_roll < player2_roll;
+                            break;
+                        }
+                    }
                 }
-            }
-        }
 
-        interaction
-            .send_embed(
-                ctx,
-                CreateEmbed::new()
-                    .title(comment)
-                    .field(
-                        format!(":first_place: {}", if player1_won { name1 } else { name2 }),
-                        if player1_won {
-                            &result_text1
-                        } else {
-                            &result_text2
-                        },
-                        false,
-                    )
-                    .field(
-                        format!(":second_place: {}", if player1_won { name2 } else { name1 }),
-                        if player1_won {
-                            &result_text2
-                        } else {
-                            &result_text1
-                        },
-                        false,
-                    ),
-            )
+                (result_text1, result_text2, player1_won)
+            })
             .await?;
 
+        let record = OpCommand::record(
+            interaction,
+            comment,
+            (name1, status1),
+            (name2, status2),
+            format!("{} / {}", result_text1, result_text2),
+            Some(if player1_won { name1 } else { name2 }),
+        );
+
+        RevealComponent::send_roll(
+            ctx,
+            interaction,
+            CreateEmbed::new()
+                .title(comment)
+                .field(
+                    format!(":first_place: {}", if player1_won { name1 } else { name2 }),
+                    if player1_won {
+                        &result_text1
+                    } else {
+                        &result_text2
+                    },
+                    false,
+                )
+                .field(
+                    format!(":second_place: {}", if player1_won { name2 } else { name1 }),
+                    if player1_won {
+                        &result_text2
+                    } else {
+                        &result_text1
+                    },
+                    false,
+                ),
+            Vec::new(),
+            &[record],
+        )
+        .await?;
+
+        Ok(CommandStatus::Ok)
+    }
+}
+
+impl OpCommand {
+    /// Creates a record of the opposed roll for the session log.
+    /// `winner` is `None` if nobody wins.
+    fn record(
+        interaction: &CommandInteraction,
+        comment: &str,
+        (name1, status1): (&str, i32),
+        (name2, status2): (&str, i32),
+        dice: String,
+        winner: Option<&str>,
+    ) -> RollRecord {
+        RollRecord {
+            roller: interaction.get_nickname(),
+            expression: format!(
+                "{}: {} ({}) vs {} ({})",
+                comment, name1, status1, name2, status2
+            ),
+            dice,
+            result: match winner {
+                Some(winner) => format!("{} wins", winner),
+                None => "Nobody wins".to_string(),
+            },
+            tier: None,
+        }
+    }
+
+    /// Does an opposed roll following Delta Green or BRP.
+    /// Both roll against their own values, and the better result wins.
+    async fn execute_rule(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        system: GameSystem,
+    ) -> Result<CommandStatus> {
+        let status1 = interaction.get_int_option("status1".into()).unwrap();
+        let status2 = interaction.get_int_option("status2".into()).unwrap();
+
+        let name1 = interaction
+            .get_string_option("name1".into())
+            .unwrap_or("player1");
+        let name2 = interaction
+            .get_string_option("name2".into())
+            .unwrap_or("player2");
+
+        let comment = interaction
+            .get_string_option("comment".into())
+            .unwrap_or("A competition");
+
+        let rule = system.rule();
+        let (outcome1, outcome2) = DiceSource::with_guild(interaction.guild_id, |dice| {
+            (rule.roll(status1, dice), rule.roll(status2, dice))
+        })
+        .await?;
+
+        let result1 = format!("{} {}", rule.label(outcome1.tier), outcome1.describe());
+        let result2 = format!("{} {}", rule.label(outcome2.tier), outcome2.describe());
+
+        let ordering = rules::compare_opposed(&outcome1, &outcome2);
+        let record = OpCommand::record(
+            interaction,
+            comment,
+            (name1, status1),
+            (name2, status2),
+            format!("{} / {}", outcome1.rolled_text, outcome2.rolled_text),
+            match ordering {
+                Ordering::Greater => Some(name1),
+                Ordering::Less => Some(name2),
+                Ordering::Equal => None,
+            },
+        );
+
+        let embed = CreateEmbed::new().title(comment);
+        let embed = match ordering {
+            Ordering::Greater => embed
+                .field(format!(":first_place: {}", name1), result1, false)
+                .field(format!(":second_place: {}", name2), result2, false),
+            Ordering::Less => embed
+                .field(format!(":first_place: {}", name2), result2, false)
+                .field(format!(":second_place: {}", name1), result1, false),
+            Ordering::Equal => embed
+                .description("Nobody wins.")
+                .field(format!(":heavy_minus_sign: {}", name1), result1, false)
+                .field(format!(":heavy_minus_sign: {}", name2), result2, false),
+        };
+
+        RevealComponent::send_roll(ctx, interaction, embed, Vec::new(), &[record]).await?;
+
         Ok(CommandStatus::Ok)
     }
 }
+
+#[naming]
+#[serenity::async_trait]
+impl BotCommand for OpCommand {
+    fn create(&self) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description("Does an opposed roll following the game system of this server (default: CoC 6th Edition).")
+            .description_localized("ja", "このサーバーのゲームシステムに基づいて対抗ロールをします (既定: 第6版).")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "status1",
+                    "A status or a skill of player1",
+                )
+                .name_localized("ja", "参加者1")
+                .description_localized("ja", "参加者1のステータスまたは技能値")
+                .required(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "status2",
+                    "A status or a skill of player2",
+                )
+                .name_localized("ja", "参加者2")
+                .description_localized("ja", "参加者2のステータスまたは技能値")
+                .required(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "bonus1",
+                    "A bonus or penalty dice for player1. Only for the CoC 7th Edition.",
+                )
+                .name_localized("ja", "ボーナス1")
+                .description_localized("ja", "参加者1へのボーナスダイスまたはペナルティダイス (第7版のみ)")
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "bonus2",
+                    "A bonus or penalty dice for player2. Only for the CoC 7th Edition.",
+                )
+                .name_localized("ja", "ボーナス2")
+                .description_localized("ja", "参加者2へのボーナスダイスまたはペナルティダイス (第7版のみ)")
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "name1",
+                    "A name of player1",
+                )
+                .name_localized("ja", "名前1")
+                .description_localized("ja", "参加者1の名前")
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "name2",
+                    "A name of player2",
+                )
+                .name_localized("ja", "名前2")
+                .description_localized("ja", "参加者2の名前")
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "comment",
+                    "A comment for the roll",
+                )
+                .name_localized("ja", "コメント")
+                .description_localized("ja", "ダイスの説明")
+                .set_autocomplete(true),
+            )
+            .add_option(RevealComponent::secret_option())
+    }
+
+    async fn autocomplete(
+        &self,
+        _ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<Vec<String>> {
+        SkillCommand::suggest(interaction).await
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let system = SettingsCommand::system_of(interaction.guild_id).await?;
+        if system == GameSystem::Coc7th {
+            return Op7Command.execute(ctx, interaction).await;
+        }
+
+        let bonus1 = interaction.get_int_option("bonus1".into());
+        let bonus2 = interaction.get_int_option("bonus2".into());
+        if bonus1.is_some() || bonus2.is_some() {
+            return Ok(CommandStatus::Err(format!(
+                "Bonus dice are not available in {}.",
+                system.name()
+            )));
+        }
+
+        match system {
+            GameSystem::Coc6th => Op6Command.execute(ctx, interaction).await,
+            system => OpCommand::execute_rule(ctx, interaction, system).await,
+        }
+    }
+}
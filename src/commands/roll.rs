@@ -1,20 +1,181 @@
 use anyhow::Result;
 use log::debug;
+use once_cell::sync::Lazy;
+use regex::Regex;
 use serenity::builder::{CreateCommand, CreateCommandOption, CreateEmbed};
 use serenity::model::application::{CommandInteraction, CommandOptionType};
 use serenity::prelude::Context;
-use tyche::dice::roller::FastRand;
+use tyche::dice::roller::Roller;
 use tyche::expr::Describe;
 use tyche::Expr;
 
-use crate::commands::{BotCommand, CommandStatus, InteractionUtil, SendEmbed};
+use crate::commands::secret::RevealComponent;
+use crate::commands::{BotCommand, CommandStatus, InteractionUtil};
+use crate::dice::DiceSource;
+use crate::rules::SkillTier;
+use crate::storage::session::RollRecord;
 
 /// A maximum number of dices that can be rolled at once.
 const MAX_DICE_NUM: usize = 30;
 
+/// The maximum number of dice rolled by `/roll`, including all of the repeats.
+const MAX_TOTAL_DICE: usize = 100;
+
+/// The maximum number of repeats of `/roll`.
+const MAX_REPEATS: u32 = 20;
+
+/// Matches a repetition such as `5#1d100` or `x5 3d6`.
+static REPEAT: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?:(\d+)\s*#|[xX×]\s*(\d+)\s)\s*(.+)$").unwrap());
+
+/// Matches a comparison with a target such as `1d100<=45`.
+static TARGET: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(.+?)\s*(<=|>=|==|!=|<>|<|>|=)\s*(-?\d+)$").unwrap());
+
 /// A command to roll dices.
 pub struct RollCommand;
 
+/// A comparison between the result and the target.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Comparison {
+    Le,
+    Lt,
+    Ge,
+    Gt,
+    Eq,
+    Ne,
+}
+
+impl Comparison {
+    fn from_symbol(symbol: &str) -> Option<Self> {
+        match symbol {
+            "<=" => Some(Comparison::Le),
+            "<" => Some(Comparison::Lt),
+            ">=" => Some(Comparison::Ge),
+            ">" => Some(Comparison::Gt),
+            "=" | "==" => Some(Comparison::Eq),
+            "!=" | "<>" => Some(Comparison::Ne),
+            _ => None,
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            Comparison::Le => "<=",
+            Comparison::Lt => "<",
+            Comparison::Ge => ">=",
+            Comparison::Gt => ">",
+            Comparison::Eq => "=",
+            Comparison::Ne => "!=",
+        }
+    }
+
+    fn check(&self, value: i32, target: i32) -> bool {
+        match self {
+            Comparison::Le => value <= target,
+            Comparison::Lt => value < target,
+            Comparison::Ge => value >= target,
+            Comparison::Gt => value > target,
+            Comparison::Eq => value == target,
+            Comparison::Ne => value != target,
+        }
+    }
+}
+
+/// A roll designated by the user, which is read before the expression is passed to Tyche.
+#[derive(Debug, PartialEq, Eq)]
+pub struct RollRequest {
+    repeat: u32,
+    expr: Expr,
+    target: Option<(Comparison, i32)>,
+}
+
+impl RollRequest {
+    /// Reads a roll such as `1d100`, `5#1d100<=45` or `x3 2d6>=7`.
+    fn parse(text: &str) -> std::result::Result<Self, String> {
+        let text = text.trim();
+
+        let (repeat, text) = match REPEAT.captures(text) {
+            Some(captures) => {
+                let repeat = captures
+                    .get(1)
+                    .or_else(|| captures.get(2))
+                    .unwrap()
+                    .as_str()
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|repeat| (1..=MAX_REPEATS).contains(repeat))
+                    .ok_or_else(|| format!("Repeat the roll 1-{} times.", MAX_REPEATS))?;
+                (repeat, captures.get(3).unwrap().as_str())
+            }
+            None => (1, text),
+        };
+
+        let (text, target) = match TARGET.captures(text) {
+            Some(captures) => {
+                let comparison = Comparison::from_symbol(&captures[2]).unwrap();
+                let target = captures[3]
+                    .parse::<i32>()
+                    .map_err(|_| format!("The target is too large: {}", &captures[3]))?;
+                (
+                    captures.get(1).unwrap().as_str(),
+                    Some((comparison, target)),
+                )
+            }
+            None => (text, None),
+        };
+
+        let expr: Expr = text
+            .parse()
+            .map_err(|err: tyche::parse::Error| err.to_string())?;
+
+        if RollRequest::count_dice(&expr) * repeat as usize > MAX_TOTAL_DICE {
+            return Err(format!(
+                "Up to {} dice can be rolled at once, including the repeats.",
+                MAX_TOTAL_DICE
+            ));
+        }
+
+        Ok(RollRequest {
+            repeat,
+            expr,
+            target,
+        })
+    }
+
+    /// Counts the dice in the expression.
+    pub fn count_dice(expr: &Expr) -> usize {
+        match expr {
+            Expr::Dice(dice) => dice.count as usize,
+            Expr::Neg(x) => RollRequest::count_dice(x),
+            Expr::Add(a, b)
+            | Expr::Sub(a, b)
+            | Expr::Mul(a, b)
+            | Expr::DivDown(a, b)
+            | Expr::DivUp(a, b) => RollRequest::count_dice(a) + RollRequest::count_dice(b),
+            _ => 0,
+        }
+    }
+
+    /// Checks the result against the target. Returns `None` if there is no target.
+    fn tier(&self, value: i32) -> Option<SkillTier> {
+        self.target.map(|(comparison, target)| {
+            if comparison.check(value, target) {
+                SkillTier::Success
+            } else {
+                SkillTier::Failure
+            }
+        })
+    }
+
+    /// Describes the target such as `<= 45`.
+    fn target_text(&self) -> String {
+        self.target
+            .map(|(comparison, target)| format!("{} {}", comparison.symbol(), target))
+            .unwrap_or_default()
+    }
+}
+
 #[naming]
 #[serenity::async_trait]
 impl BotCommand for RollCommand {
@@ -26,10 +187,10 @@ impl BotCommand for RollCommand {
                 CreateCommandOption::new(
                     CommandOptionType::String,
                     "dice",
-                    "An expression to be evaluated (ex. `3d4 + 1`)",
+                    "An expression to be evaluated (ex. `3d4 + 1`, `1d100<=45`, `5#2d6`)",
                 )
                 .name_localized("ja", "ダイス")
-                .description_localized("ja", "振りたいダイス (例: `3d4 + 1`)")
+                .description_localized("ja", "振りたいダイス (例: `3d4 + 1`, `1d100<=45`, `5#2d6`)")
                 .required(true),
             )
             .add_option(
@@ -41,6 +202,7 @@ impl BotCommand for RollCommand {
                 .name_localized("ja", "コメント")
                 .description_localized("ja", "ダイスの説明"),
             )
+            .add_option(RevealComponent::secret_option())
     }
 
     async fn execute(
@@ -55,46 +217,122 @@ impl BotCommand for RollCommand {
             .map(|comment| format!(" for {}", comment))
             .unwrap_or_default();
 
-        match RollCommand::evaluate_dice_expr(dice) {
-            Ok((evaluated, description)) => {
-                interaction
-                    .send_embed(
-                        ctx,
-                        CreateEmbed::new()
-                            .title(format!(
-                                "{} rolls dice(s){}",
-                                interaction.get_nickname(),
-                                comment
-                            ))
-                            .field(format!(":game_die: {}", evaluated), description, false),
-                    )
-                    .await?;
-
-                Ok(CommandStatus::Ok)
-            }
+        let evaluated = match RollRequest::parse(dice) {
+            Ok(request) => DiceSource::with_guild(interaction.guild_id, |source| {
+                (0..request.repeat)
+                    .map(|_| RollCommand::evaluate(&request.expr, source))
+                    .collect::<std::result::Result<Vec<_>, _>>()
+            })
+            .await?
+            .map(|results| (request, results)),
+            Err(message) => Err(message),
+        };
+        let (request, results) = match evaluated {
+            Ok(evaluated) => evaluated,
             Err(message) => {
                 debug!(
                     "Failed to evaluate dice expression: {} (reason: {})",
                     dice, message
                 );
 
-                Ok(CommandStatus::Err(message))
+                return Ok(CommandStatus::Err(message));
+            }
+        };
+
+        let records = results
+            .iter()
+            .map(|(evaluated, description)| {
+                let tier = request.tier(*evaluated);
+                RollRecord {
+                    roller: interaction.get_nickname(),
+                    expression: dice.to_string(),
+                    dice: description.clone(),
+                    result: match tier {
+                        Some(tier) => format!("{} ({})", evaluated, tier.name()),
+                        None => evaluated.to_string(),
+                    },
+                    tier,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let mut embed = CreateEmbed::new().title(format!(
+            "{} rolls dice(s){}",
+            interaction.get_nickname(),
+            comment
+        ));
+        if let [(evaluated, description)] = results.as_slice() {
+            embed = embed.field(format!(":game_die: {}", evaluated), description, false);
+            if let Some(tier) = request.tier(*evaluated) {
+                embed = embed.field(
+                    tier.label(),
+                    format!("{} {}", evaluated, request.target_text()),
+                    false,
+                );
+            }
+        } else {
+            let lines = results
+                .iter()
+                .enumerate()
+                .map(|(index, (evaluated, description))| {
+                    let tier = request
+                        .tier(*evaluated)
+                        .map(|tier| format!(" {}", tier.label()))
+                        .unwrap_or_default();
+                    format!(
+                        "#{} :game_die: **{}** ({}){}",
+                        index + 1,
+                        evaluated,
+                        description,
+                        tier
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            embed = embed.description(lines);
+
+            if request.target.is_some() {
+                let successes = results
+                    .iter()
+                    .filter(|(evaluated, _)| request.tier(*evaluated) == Some(SkillTier::Success))
+                    .count();
+                embed = embed.field(
+                    "Successes",
+                    format!(
+                        "{} / {} ({})",
+                        successes,
+                        results.len(),
+                        request.target_text()
+                    ),
+                    false,
+                );
             }
         }
+
+        RevealComponent::send_roll(ctx, interaction, embed, Vec::new(), &records).await?;
+
+        Ok(CommandStatus::Ok)
     }
 }
 
 impl RollCommand {
-    /// Evaluates a dice expression.
-    pub fn evaluate_dice_expr(expr: &str) -> Result<(i32, String), String> {
+    /// Evaluates a dice expression with the given roller.
+    pub fn evaluate_dice_expr(
+        expr: &str,
+        roller: &mut impl Roller,
+    ) -> Result<(i32, String), String> {
         // Parse the expression.
         let expr: Expr = expr
             .parse()
             .map_err(|err: tyche::parse::Error| err.to_string())?;
 
+        RollCommand::evaluate(&expr, roller)
+    }
+
+    /// Evaluates a parsed expression with the given roller.
+    fn evaluate(expr: &Expr, roller: &mut impl Roller) -> Result<(i32, String), String> {
         // Evaluate the expression.
-        let mut roller = FastRand::default();
-        let result = expr.eval(&mut roller).map_err(|err| err.to_string())?;
+        let result = expr.eval(roller).map_err(|err| err.to_string())?;
 
         // Calculate the result.
         let evaluated = result.calc().map_err(|err| err.to_string())?;
@@ -102,3 +340,45 @@ impl RollCommand {
         Ok((evaluated, result.describe(Some(MAX_DICE_NUM))))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tyche::dice::roller::Max;
+
+    #[test]
+    fn parse_repeats_and_targets() {
+        let request = RollRequest::parse("5#1d100<=45").unwrap();
+        assert_eq!(request.repeat, 5);
+        assert_eq!(request.expr, "1d100".parse().unwrap());
+        assert_eq!(request.target, Some((Comparison::Le, 45)));
+        assert_eq!(request.tier(45), Some(SkillTier::Success));
+        assert_eq!(request.tier(46), Some(SkillTier::Failure));
+
+        let request = RollRequest::parse("x3 2d6 + 1 >= 7").unwrap();
+        assert_eq!(request.repeat, 3);
+        assert_eq!(request.expr, "2d6 + 1".parse().unwrap());
+        assert_eq!(request.target_text(), ">= 7");
+
+        let request = RollRequest::parse("3d4 + 1").unwrap();
+        assert_eq!(request.repeat, 1);
+        assert_eq!(request.target, None);
+        assert_eq!(request.tier(10), None);
+
+        assert!(RollRequest::parse("0#1d6").is_err());
+        assert!(RollRequest::parse("21#1d6").is_err());
+        assert!(RollRequest::parse("1d6<=").is_err());
+    }
+
+    #[test]
+    fn combined_dice_limit() {
+        assert!(RollRequest::parse("10#10d6").is_ok());
+        assert!(RollRequest::parse("10#10d6+1d4").is_err());
+        assert!(RollRequest::parse("101d6").is_err());
+
+        let request = RollRequest::parse("4#2d6>6").unwrap();
+        let (evaluated, _) = RollCommand::evaluate(&request.expr, &mut Max).unwrap();
+        assert_eq!(evaluated, 12);
+        assert_eq!(request.tier(evaluated), Some(SkillTier::Success));
+    }
+}
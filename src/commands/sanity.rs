@@ -0,0 +1,245 @@
+use anyhow::Result;
+use serenity::builder::{CreateCommand, CreateCommandOption, CreateEmbed};
+use serenity::model::application::{CommandInteraction, CommandOptionType};
+use serenity::prelude::Context;
+use tyche::dice::roller::Max;
+
+use crate::commands::roll::RollCommand;
+use crate::commands::session::SessionCommand;
+use crate::commands::stats::StatsCommand;
+use crate::commands::{BotCommand, CommandStatus, InteractionUtil, SendEmbed};
+use crate::dice::DiceSource;
+use crate::rules::{GameSystem, SkillTier};
+use crate::storage::session::RollRecord;
+use crate::vitals::TEMPORARY_INSANITY_LOSS;
+
+/// A command that does a sanity check.
+pub struct SanCommand;
+
+impl SanCommand {
+    /// Splits the SAN loss such as `1/1d6` into the ones on success and on failure.
+    /// Both are checked before the roll so that an invalid loss does not consume the dice.
+    /// A `/` can also be a division, so the loss is split only where both sides are valid.
+    fn parse_loss(loss: &str) -> std::result::Result<(&str, &str), String> {
+        let is_valid = |expr: &str| {
+            !expr.trim().is_empty() && RollCommand::evaluate_dice_expr(expr, &mut Max).is_ok()
+        };
+
+        let mut depth = 0;
+        let mut splits = Vec::new();
+        for (index, c) in loss.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                '/' if depth == 0 => {
+                    let (success, failure) = (&loss[..index], &loss[index + 1..]);
+                    if is_valid(success) && is_valid(failure) {
+                        splits.push((success.trim(), failure.trim()));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        match splits.as_slice() {
+            [split] => Ok(*split),
+            [] => Err(format!(
+                "SAN loss `{}` is invalid. Write the losses on success and failure like `1/1d6`.",
+                loss
+            )),
+            _ => Err(format!(
+                "SAN loss `{}` can be read in more than one way. Wrap the division in parentheses (ex. `(1d6/2)/1d10`).",
+                loss
+            )),
+        }
+    }
+
+    /// Calculates the SAN actually lost and the new SAN. SAN does not go below 0.
+    fn apply_loss(san: i32, lost: i32) -> (i32, i32) {
+        let new_san = (san - lost.max(0)).max(0);
+        (san - new_san, new_san)
+    }
+}
+
+#[naming]
+#[serenity::async_trait]
+impl BotCommand for SanCommand {
+    fn create(&self) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description("Does a sanity check.")
+            .description_localized("ja", "正気度ロールを行います.")
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::Integer, "san", "Current SAN")
+                    .description_localized("ja", "現在の正気度")
+                    .required(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "loss",
+                    "SAN loss on success/failure (ex. `1/1d6`)",
+                )
+                .name_localized("ja", "減少値")
+                .description_localized("ja", "成功時/失敗時の正気度の減少値 (例: `1/1d6`)")
+                .required(true),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "edition",
+                    "An edition of the rule (default: 6th)",
+                )
+                .name_localized("ja", "版")
+                .description_localized("ja", "ルールの版 (既定: 第6版)")
+                .add_string_choice("6th", "6th")
+                .add_string_choice("7th", "7th"),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::String, "comment", "A comment")
+                    .description_localized("ja", "ダイスの説明"),
+            )
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let san = interaction.get_int_option("san".into()).unwrap();
+        if !(0..=99).contains(&san) {
+            return Ok(CommandStatus::Err("SAN must be 0-99.".to_string()));
+        }
+
+        let loss = interaction.get_string_option("loss".into()).unwrap();
+        let (success_loss, failure_loss) = match SanCommand::parse_loss(loss) {
+            Ok(parsed) => parsed,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+
+        let is_7th = interaction.get_string_option("edition".into()) == Some("7th");
+
+        let comment = interaction
+            .get_string_option("comment".into())
+            .map(|comment| format!(" for {}", comment))
+            .unwrap_or_default();
+
+        let system = if is_7th {
+            GameSystem::Coc7th
+        } else {
+            GameSystem::Coc6th
+        };
+        let rule = system.rule();
+        let outcome =
+            DiceSource::with_guild(interaction.guild_id, |dice| rule.roll(san, dice)).await?;
+        StatsCommand::record(
+            interaction.guild_id,
+            interaction.user.id,
+            system,
+            &outcome,
+            0,
+            false,
+        )
+        .await?;
+        let succeeded = outcome.tier.is_success();
+
+        // A fumble in 7th Edition costs the maximum loss.
+        let fumbled = is_7th && outcome.tier == SkillTier::Fumble;
+
+        let expr = if succeeded {
+            success_loss
+        } else {
+            failure_loss
+        };
+        let evaluated = if fumbled {
+            RollCommand::evaluate_dice_expr(expr, &mut Max)
+                .map(|(lost, description)| (lost, format!("max({})", description)))
+        } else {
+            DiceSource::with_guild(interaction.guild_id, |dice| {
+                RollCommand::evaluate_dice_expr(expr, dice)
+            })
+            .await?
+        };
+        let (lost, description) = match evaluated {
+            Ok(evaluated) => evaluated,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+
+        let (lost, new_san) = SanCommand::apply_loss(san, lost);
+
+        let mut embed = CreateEmbed::new()
+            .title(format!(
+                "{} does a sanity check{}",
+                interaction.get_nickname(),
+                comment
+            ))
+            .field(rule.label(outcome.tier), outcome.describe(), false)
+            .field(format!(":brain: SAN -{}", lost), &description, false)
+            .field(
+                format!(":heart: SAN {}", new_san),
+                format!("{} - {} = {}", san, lost, new_san),
+                false,
+            );
+
+        if lost >= TEMPORARY_INSANITY_LOSS {
+            embed = embed.field(
+                ":warning: **Temporary insanity?**",
+                if is_7th {
+                    "Lost 5 or more SAN at once. Make an INT roll."
+                } else {
+                    "Lost 5 or more SAN at once. Make an Idea roll."
+                },
+                false,
+            );
+        }
+
+        let record = RollRecord {
+            roller: interaction.get_nickname(),
+            expression: format!("SAN check ({}), loss {}", san, loss),
+            dice: format!("{} / {}", outcome.rolled_text, description),
+            result: format!("SAN {} -> {}", san, new_san),
+            tier: Some(outcome.tier),
+        };
+        SessionCommand::record_command(interaction, &[record]).await?;
+
+        interaction.send_embed(ctx, embed).await?;
+
+        Ok(CommandStatus::Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_loss() {
+        assert_eq!(SanCommand::parse_loss("1/1d6"), Ok(("1", "1d6")));
+        assert_eq!(SanCommand::parse_loss(" 0 / 1d10+1 "), Ok(("0", "1d10+1")));
+
+        assert!(SanCommand::parse_loss("1d6").is_err());
+        assert!(SanCommand::parse_loss("1/").is_err());
+        assert!(SanCommand::parse_loss("/1d6").is_err());
+
+        // Both sides are checked even if the roll would use only one of them.
+        assert!(SanCommand::parse_loss("1/abc").is_err());
+        assert!(SanCommand::parse_loss("abc/1d6").is_err());
+
+        // A division is told from the separator by the parentheses.
+        assert_eq!(
+            SanCommand::parse_loss("(1d6/2)/1d10"),
+            Ok(("(1d6/2)", "1d10"))
+        );
+        assert_eq!(SanCommand::parse_loss("1/(1d10/2)"), Ok(("1", "(1d10/2)")));
+        let ambiguous = SanCommand::parse_loss("1d6/2/1d10").unwrap_err();
+        assert!(ambiguous.contains("parentheses"));
+    }
+
+    #[test]
+    fn apply_loss() {
+        assert_eq!(SanCommand::apply_loss(60, 5), (5, 55));
+        // Only the SAN left can be lost.
+        assert_eq!(SanCommand::apply_loss(3, 5), (3, 0));
+        assert_eq!(SanCommand::apply_loss(60, -2), (0, 60));
+        assert_eq!(SanCommand::apply_loss(0, 0), (0, 0));
+    }
+}
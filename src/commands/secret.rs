@@ -0,0 +1,222 @@
+use anyhow::Result;
+use serenity::builder::{
+    CreateActionRow, CreateButton, CreateCommandOption, CreateEmbed, CreateEmbedFooter,
+    CreateInteractionResponse, CreateInteractionResponseFollowup, CreateInteractionResponseMessage,
+};
+use serenity::model::application::{
+    ActionRowComponent, ButtonKind, ButtonStyle, CommandInteraction, CommandOptionType,
+    ComponentInteraction,
+};
+use serenity::model::channel::{Embed, Message};
+use serenity::prelude::Context;
+
+use crate::commands::session::SessionCommand;
+use crate::commands::{BotComponent, CommandStatus, InteractionUtil, SendEmbed};
+use crate::storage::secret::SecretRoll;
+use crate::storage::session::RollRecord;
+use crate::storage::Storage;
+
+/// A button to publish the result of a secret roll.
+pub struct RevealComponent;
+
+impl RevealComponent {
+    /// Creates the `secret` option of the rolls.
+    pub fn secret_option() -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::Boolean,
+            "secret",
+            "Shows the result only to you (default: false)",
+        )
+        .name_localized("ja", "シークレット")
+        .description_localized("ja", "結果を自分にだけ表示します (既定: false)")
+    }
+
+    /// Checks whether the `secret` option is set.
+    pub fn is_secret(interaction: &CommandInteraction) -> bool {
+        interaction.get_bool_option("secret".into()) == Some(true)
+    }
+
+    /// Sends the result of the roll and records it in the session running in the channel.
+    /// If the `secret` option is set, only the user sees the result and the others see a notice.
+    pub async fn send_roll(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        embed: CreateEmbed,
+        mut components: Vec<CreateActionRow>,
+        records: &[RollRecord],
+    ) -> Result<()> {
+        let secret = RevealComponent::is_secret(interaction);
+        SessionCommand::record(
+            interaction.channel_id,
+            interaction.user.id,
+            &interaction.data.name,
+            secret,
+            records,
+        )
+        .await?;
+
+        if !secret {
+            return interaction
+                .send_embed_with_components(ctx, embed, components)
+                .await;
+        }
+
+        let owner_id = interaction.user.id;
+        let json = serde_json::to_string(&embed)?;
+        let id =
+            Storage::with(|connection| SecretRoll::insert(connection, owner_id, &json)).await?;
+
+        components.push(RevealComponent::button(id));
+
+        interaction
+            .create_response(
+                &ctx,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::default()
+                        .add_embed(embed)
+                        .components(components)
+                        .ephemeral(true),
+                ),
+            )
+            .await?;
+
+        interaction
+            .create_followup(
+                &ctx,
+                CreateInteractionResponseFollowup::new().add_embed(
+                    CreateEmbed::new()
+                        .title(format!(
+                            ":see_no_evil: {} rolled in secret",
+                            interaction.get_nickname()
+                        ))
+                        .description("The result will be shown when it is revealed."),
+                ),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    /// Keeps the Reveal button on a secret result updated by another component (ex. Push).
+    /// The stored result is replaced with the new one.
+    /// Returns the components to put on the updated message.
+    pub async fn keep(message: &Message, embed: &CreateEmbed) -> Result<Vec<CreateActionRow>> {
+        let id = message
+            .components
+            .iter()
+            .flat_map(|row| row.components.iter())
+            .find_map(|component| match component {
+                ActionRowComponent::Button(button) => match &button.data {
+                    ButtonKind::NonLink { custom_id, .. } => custom_id
+                        .strip_prefix(&format!("{}:", RevealComponent.name()))
+                        .and_then(|id| id.parse::<i64>().ok()),
+                    _ => None,
+                },
+                _ => None,
+            });
+
+        match id {
+            Some(id) => {
+                let json = serde_json::to_string(embed)?;
+                Storage::with(|connection| SecretRoll::update(connection, id, &json)).await?;
+                Ok(vec![RevealComponent::button(id)])
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Creates a Reveal button of the result.
+    fn button(id: i64) -> CreateActionRow {
+        CreateActionRow::Buttons(vec![CreateButton::new(
+            RevealComponent.custom_id(&[id.to_string()]),
+        )
+        .label("Reveal")
+        .emoji('👁')
+        .style(ButtonStyle::Secondary)])
+    }
+}
+
+#[serenity::async_trait]
+impl BotComponent for RevealComponent {
+    fn name(&self) -> &str {
+        "reveal"
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &ComponentInteraction,
+        args: &[&str],
+    ) -> Result<CommandStatus> {
+        let id: i64 = match args {
+            [id] => id.parse()?,
+            _ => return Ok(CommandStatus::Err("The button is broken.".to_string())),
+        };
+
+        let roll = Storage::with(|connection| SecretRoll::find(connection, id)).await?;
+        let roll = match roll {
+            Some(roll) => roll,
+            None => return Ok(CommandStatus::Err("The result is missing.".to_string())),
+        };
+
+        if interaction.user.id != roll.owner_id {
+            return Ok(CommandStatus::Err(
+                "Only the user who rolled the dice can reveal it.".to_string(),
+            ));
+        }
+
+        let revealed = Storage::with(|connection| SecretRoll::reveal(connection, id)).await?;
+        if !revealed {
+            return Ok(CommandStatus::Err(
+                "This roll has already been revealed.".to_string(),
+            ));
+        }
+
+        let embed: Embed = serde_json::from_str(&roll.embed)?;
+
+        // Other buttons (ex. Push) are no longer available once the result is public.
+        interaction
+            .create_response(
+                &ctx,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::default().components(Vec::new()),
+                ),
+            )
+            .await?;
+
+        interaction
+            .create_followup(
+                &ctx,
+                CreateInteractionResponseFollowup::new().add_embed(
+                    CreateEmbed::from(embed)
+                        .footer(CreateEmbedFooter::new("Rolled in secret and revealed")),
+                ),
+            )
+            .await?;
+
+        Ok(CommandStatus::Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::model::colour::Colour;
+
+    use super::*;
+
+    #[test]
+    fn stored_embed_can_be_restored() {
+        let embed = CreateEmbed::new()
+            .title("Alice uses Psychology")
+            .field(":o: Success", "42 <= 60", false)
+            .colour(Colour::RED);
+
+        let json = serde_json::to_string(&embed).unwrap();
+        let restored: Embed = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.title.as_deref(), Some("Alice uses Psychology"));
+        assert_eq!(restored.fields[0].name, ":o: Success");
+        assert_eq!(restored.fields[0].value, "42 <= 60");
+        assert_eq!(restored.colour, Some(Colour::RED));
+    }
+}
@@ -0,0 +1,110 @@
+use anyhow::Result;
+use serenity::builder::{
+    CreateCommand, CreateCommandOption, CreateEmbed, CreateInteractionResponse,
+    CreateInteractionResponseMessage,
+};
+use serenity::model::application::{CommandInteraction, CommandOptionType, InteractionContext};
+use serenity::model::permissions::Permissions;
+use serenity::prelude::Context;
+
+use crate::commands::{BotCommand, CommandStatus, InteractionUtil, SendEmbed};
+use crate::storage::seed::GuildSeed;
+use crate::storage::Storage;
+
+/// A command that sets a seed of the dice in the server to make the rolls replayable.
+/// The seed is told only to the user who set it, and revealed to everyone when it is cleared.
+pub struct SeedCommand;
+
+#[naming]
+#[serenity::async_trait]
+impl BotCommand for SeedCommand {
+    fn create(&self) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description("Sets a seed of the dice in this server to make the rolls replayable.")
+            .description_localized(
+                "ja",
+                "このサーバーのダイスのシード値を設定し, ロールを再現できるようにします.",
+            )
+            .default_member_permissions(Permissions::MANAGE_GUILD)
+            .contexts(vec![InteractionContext::Guild])
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "value",
+                    "A seed of the dice (Leave it empty to stop using a seed.)",
+                )
+                .name_localized("ja", "シード値")
+                .description_localized(
+                    "ja",
+                    "ダイスのシード値 (空欄にするとシード値の使用をやめます.)",
+                )
+                .min_int_value(0)
+                .max_int_value(i32::MAX as u64),
+            )
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let guild_id = match interaction.guild_id {
+            Some(guild_id) => guild_id,
+            None => {
+                return Ok(CommandStatus::Err(
+                    "This command can only be used in a server.".to_string(),
+                ))
+            }
+        };
+
+        let seed = interaction
+            .get_int_option("value".into())
+            .map(|seed| seed as u64);
+
+        match seed {
+            Some(seed) => {
+                Storage::with(|connection| GuildSeed::set(connection, guild_id, seed)).await?;
+
+                // Only the user who set the seed sees it not to let the players predict the rolls.
+                let embed = CreateEmbed::new()
+                    .title(format!(":seedling: The dice seed is set to {}", seed))
+                    .description(
+                        "The following rolls in this server can be replayed from the seed. \
+                         It will be revealed to everyone when the seed is cleared.",
+                    );
+                interaction
+                    .create_response(
+                        &ctx,
+                        CreateInteractionResponse::Message(
+                            CreateInteractionResponseMessage::default()
+                                .add_embed(embed)
+                                .ephemeral(true),
+                        ),
+                    )
+                    .await?;
+            }
+            None => {
+                let cleared =
+                    Storage::with(|connection| GuildSeed::clear(connection, guild_id)).await?;
+
+                let embed = match cleared {
+                    Some(cleared) => CreateEmbed::new()
+                        .title(":seedling: The dice seed is cleared")
+                        .description(format!(
+                            "The rolls so far were drawn from the seed {}. \
+                             The following rolls in this server use a secure random source.",
+                            cleared.seed
+                        )),
+                    None => CreateEmbed::new()
+                        .title(":seedling: The dice seed is cleared")
+                        .description(
+                            "The following rolls in this server use a secure random source.",
+                        ),
+                };
+                interaction.send_embed(ctx, embed).await?;
+            }
+        }
+
+        Ok(CommandStatus::Ok)
+    }
+}
@@ -0,0 +1,412 @@
+use anyhow::Result;
+use serenity::builder::{CreateAttachment, CreateCommand, CreateCommandOption, CreateEmbed};
+use serenity::model::application::{CommandInteraction, CommandOptionType};
+use serenity::model::id::{ChannelId, UserId};
+use serenity::prelude::Context;
+
+use crate::commands::{BotCommand, CommandStatus, InteractionUtil, SendEmbed};
+use crate::storage::session::{LoggedRoll, RollRecord, Session};
+use crate::storage::Storage;
+
+/// A text shown instead of the dice and the result of a secret roll.
+const SECRET_TEXT: &str = "(secret)";
+
+/// A command that records the results of the commands in a channel.
+pub struct SessionCommand;
+
+impl SessionCommand {
+    /// Records the results of a command if a session is running in the channel.
+    pub async fn record(
+        channel_id: ChannelId,
+        user_id: UserId,
+        command: &str,
+        secret: bool,
+        records: &[RollRecord],
+    ) -> Result<()> {
+        Storage::with(|connection| {
+            for record in records {
+                Session::record(connection, channel_id, user_id, command, secret, record)?;
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    /// Records the public results of the command if a session is running in the channel.
+    pub async fn record_command(
+        interaction: &CommandInteraction,
+        records: &[RollRecord],
+    ) -> Result<()> {
+        SessionCommand::record(
+            interaction.channel_id,
+            interaction.user.id,
+            &interaction.data.name,
+            false,
+            records,
+        )
+        .await
+    }
+
+    /// Writes the session as a Markdown table.
+    fn markdown(session: &Session, rolls: &[LoggedRoll]) -> String {
+        let cell = |text: &str| text.replace('|', "\\|").replace('\n', " ");
+
+        let rows = rolls
+            .iter()
+            .map(|roll| {
+                let (dice, result) = SessionCommand::dice_and_result(roll);
+                format!(
+                    "|{}|{}|/{}|{}|{}|{}|{}|\n",
+                    roll.rolled_at,
+                    cell(&roll.record.roller),
+                    roll.command,
+                    cell(&roll.record.expression),
+                    cell(dice),
+                    cell(result),
+                    SessionCommand::tier_name(roll)
+                )
+            })
+            .collect::<String>();
+
+        format!(
+            "# Session #{}\n\n- Started: {} UTC\n- Ended: {}\n\n|Time (UTC)|Roller|Command|Expression|Dice|Result|Tier|\n|:---|:---|:---|:---|:---|:---|:---|\n{}",
+            session.id,
+            session.started_at,
+            SessionCommand::ended_text(session),
+            rows
+        )
+    }
+
+    /// Writes the session as an HTML page which does not depend on any other file.
+    fn html(session: &Session, rolls: &[LoggedRoll]) -> String {
+        let rows = rolls
+            .iter()
+            .map(|roll| {
+                let (dice, result) = SessionCommand::dice_and_result(roll);
+                // The class of a secret roll must not tell its tier either.
+                let tier = match roll.record.tier {
+                    _ if roll.secret => "secret",
+                    Some(tier) => tier.id(),
+                    None => "none",
+                };
+                format!(
+                    "<tr class=\"{}\"><td>{}</td><td>{}</td><td>/{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                    tier,
+                    escape_html(&roll.rolled_at),
+                    escape_html(&roll.record.roller),
+                    escape_html(&roll.command),
+                    escape_html(&roll.record.expression),
+                    escape_html(dice),
+                    escape_html(result),
+                    SessionCommand::tier_name(roll)
+                )
+            })
+            .collect::<String>();
+
+        format!(
+            r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Session #{id}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; background: #36393f; color: #dcddde; }}
+table {{ border-collapse: collapse; }}
+th, td {{ padding: 0.3em 0.8em; border-bottom: 1px solid #4f545c; text-align: left; }}
+.great_critical, .critical, .extreme, .special {{ color: #faa61a; }}
+.hard, .success {{ color: #43b581; }}
+.failure {{ color: #b9bbbe; }}
+.fumble, .great_fumble {{ color: #f04747; }}
+</style>
+</head>
+<body>
+<h1>Session #{id}</h1>
+<p>Started: {started} UTC<br>Ended: {ended}</p>
+<table>
+<tr><th>Time (UTC)</th><th>Roller</th><th>Command</th><th>Expression</th><th>Dice</th><th>Result</th><th>Tier</th></tr>
+{rows}</table>
+</body>
+</html>
+"#,
+            id = session.id,
+            started = escape_html(&session.started_at),
+            ended = escape_html(&SessionCommand::ended_text(session)),
+            rows = rows
+        )
+    }
+
+    /// Gets the dice and the result of the roll. They are hidden if it was rolled in secret.
+    fn dice_and_result(roll: &LoggedRoll) -> (&str, &str) {
+        if roll.secret {
+            (SECRET_TEXT, SECRET_TEXT)
+        } else {
+            (&roll.record.dice, &roll.record.result)
+        }
+    }
+
+    fn tier_name(roll: &LoggedRoll) -> &'static str {
+        match roll.record.tier {
+            Some(_) if roll.secret => SECRET_TEXT,
+            Some(tier) => tier.name(),
+            None => "",
+        }
+    }
+
+    fn ended_text(session: &Session) -> String {
+        match &session.ended_at {
+            Some(ended_at) => format!("{} UTC", ended_at),
+            None => "(running)".to_string(),
+        }
+    }
+
+    async fn execute_start(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let guild_id = interaction.guild_id;
+        let channel_id = interaction.channel_id;
+        let session =
+            Storage::with(|connection| Session::start(connection, guild_id, channel_id)).await?;
+        if session.is_none() {
+            return Ok(CommandStatus::Err(
+                "A session is already running in this channel.".to_string(),
+            ));
+        }
+
+        interaction
+            .send_embed(
+                ctx,
+                CreateEmbed::new()
+                    .title(":scroll: The session has started")
+                    .description("Every result in this channel is recorded until `/session end`."),
+            )
+            .await?;
+
+        Ok(CommandStatus::Ok)
+    }
+
+    async fn execute_end(ctx: &Context, interaction: &CommandInteraction) -> Result<CommandStatus> {
+        let channel_id = interaction.channel_id;
+        let rolls = Storage::with(|connection| {
+            Session::end(connection, channel_id)?
+                .map(|session| session.rolls(connection))
+                .transpose()
+        })
+        .await?;
+        let rolls = match rolls {
+            Some(rolls) => rolls,
+            None => {
+                return Ok(CommandStatus::Err(
+                    "No session is running in this channel.".to_string(),
+                ))
+            }
+        };
+
+        interaction
+            .send_embed(
+                ctx,
+                CreateEmbed::new()
+                    .title(":scroll: The session has ended")
+                    .description(format!(
+                        "{} result(s) recorded. Get the log with `/session export`.",
+                        rolls.len()
+                    )),
+            )
+            .await?;
+
+        Ok(CommandStatus::Ok)
+    }
+
+    async fn execute_export(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let channel_id = interaction.channel_id;
+        let log = Storage::with(
+            |connection| match Session::latest(connection, channel_id)? {
+                Some(session) => {
+                    let rolls = session.rolls(connection)?;
+                    Ok(Some((session, rolls)))
+                }
+                None => Ok(None),
+            },
+        )
+        .await?;
+        let (session, rolls) = match log {
+            Some(log) => log,
+            None => {
+                return Ok(CommandStatus::Err(
+                    "No session has been recorded in this channel.".to_string(),
+                ))
+            }
+        };
+
+        let file = match interaction.get_string_option("format".into()) {
+            Some("html") => CreateAttachment::bytes(
+                SessionCommand::html(&session, &rolls),
+                format!("session-{}.html", session.id),
+            ),
+            _ => CreateAttachment::bytes(
+                SessionCommand::markdown(&session, &rolls),
+                format!("session-{}.md", session.id),
+            ),
+        };
+
+        let embed = CreateEmbed::new()
+            .title(format!(":outbox_tray: Exported session #{}", session.id))
+            .description(format!(
+                "{} result(s) from {} UTC to {}.",
+                rolls.len(),
+                session.started_at,
+                SessionCommand::ended_text(&session)
+            ));
+
+        interaction
+            .send_embed_with_files(ctx, embed, vec![file])
+            .await?;
+
+        Ok(CommandStatus::Ok)
+    }
+}
+
+/// Escapes the text to put it in an HTML.
+fn escape_html(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&#39;".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+#[naming]
+#[serenity::async_trait]
+impl BotCommand for SessionCommand {
+    fn create(&self) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description("Records the results of the commands in this channel.")
+            .description_localized("ja", "このチャンネルのコマンドの結果を記録します.")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "start",
+                    "Starts recording the results.",
+                )
+                .description_localized("ja", "結果の記録を開始します."),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "end",
+                    "Stops recording the results.",
+                )
+                .description_localized("ja", "結果の記録を終了します."),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "export",
+                    "Exports the log of the latest session as a file.",
+                )
+                .description_localized("ja", "最新のセッションの記録をファイルに出力します.")
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "format",
+                        "A format of the file (default: Markdown)",
+                    )
+                    .name_localized("ja", "形式")
+                    .description_localized("ja", "ファイルの形式 (既定: Markdown)")
+                    .add_string_choice("Markdown", "md")
+                    .add_string_choice("HTML", "html"),
+                ),
+            )
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        match interaction.get_subcommand() {
+            Some("start") => SessionCommand::execute_start(ctx, interaction).await,
+            Some("end") => SessionCommand::execute_end(ctx, interaction).await,
+            Some("export") => SessionCommand::execute_export(ctx, interaction).await,
+            _ => Ok(CommandStatus::Err("Unknown subcommand.".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::SkillTier;
+
+    fn log() -> (Session, Vec<LoggedRoll>) {
+        let session = Session {
+            id: 3,
+            guild_id: None,
+            channel_id: ChannelId::new(1),
+            started_at: "2024-01-01 12:00:00".to_string(),
+            ended_at: None,
+        };
+        let roll = |command: &str, secret, expression: &str, tier| LoggedRoll {
+            rolled_at: "2024-01-01 12:05:00".to_string(),
+            user_id: UserId::new(1),
+            command: command.to_string(),
+            secret,
+            record: RollRecord {
+                roller: "<Harvey>".to_string(),
+                expression: expression.to_string(),
+                dice: "12".to_string(),
+                result: "12".to_string(),
+                tier,
+            },
+        };
+        let rolls = vec![
+            roll(
+                "sk7",
+                false,
+                "Spot Hidden | 目星 (60)",
+                Some(SkillTier::Hard),
+            ),
+            roll("sk7", true, "Stealth (40)", Some(SkillTier::Failure)),
+            roll("roll", false, "1d20", None),
+        ];
+        (session, rolls)
+    }
+
+    #[test]
+    fn export_markdown() {
+        let (session, rolls) = log();
+        let markdown = SessionCommand::markdown(&session, &rolls);
+
+        assert!(markdown.starts_with("# Session #3\n"));
+        assert!(markdown.contains("- Ended: (running)"));
+        assert!(markdown.contains(
+            "|2024-01-01 12:05:00|<Harvey>|/sk7|Spot Hidden \\| 目星 (60)|12|12|Hard|\n"
+        ));
+        assert!(markdown.contains("|/sk7|Stealth (40)|(secret)|(secret)|(secret)|\n"));
+        assert!(markdown.contains("|/roll|1d20|12|12||\n"));
+    }
+
+    #[test]
+    fn export_html() {
+        let (session, rolls) = log();
+        let html = SessionCommand::html(&session, &rolls);
+
+        assert!(html.contains("<title>Session #3</title>"));
+        assert!(html.contains("<td>&lt;Harvey&gt;</td>"));
+        assert!(!html.contains("<Harvey>"));
+        assert!(html.contains("<tr class=\"hard\">"));
+        assert!(html.contains("<td>(secret)</td><td>(secret)</td><td>(secret)</td>"));
+
+        // The secret Stealth roll failed, but its row does not tell it.
+        assert!(html.contains("<tr class=\"secret\"><td>2024-01-01 12:05:00</td>"));
+        assert!(!html.contains("<tr class=\"failure\">"));
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::Result;
+use serenity::builder::{CreateCommand, CreateCommandOption, CreateEmbed};
+use serenity::model::application::{CommandInteraction, CommandOptionType, InteractionContext};
+use serenity::model::id::GuildId;
+use serenity::model::permissions::Permissions;
+use serenity::prelude::Context;
+
+use crate::commands::{BotCommand, CommandStatus, InteractionUtil, SendEmbed};
+use crate::rules::{GameSystem, ALL_SYSTEMS};
+use crate::storage::settings::GuildSettings;
+use crate::storage::Storage;
+
+/// A command that changes the settings of the server.
+pub struct SettingsCommand;
+
+impl SettingsCommand {
+    /// Gets the game system of the guild. The default one is used outside of the guilds.
+    pub async fn system_of(guild_id: Option<GuildId>) -> Result<GameSystem> {
+        match guild_id {
+            Some(guild_id) => {
+                let settings =
+                    Storage::with(|connection| GuildSettings::get(connection, guild_id)).await?;
+                Ok(settings.system)
+            }
+            None => Ok(GameSystem::default()),
+        }
+    }
+
+    async fn execute_system(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        guild_id: GuildId,
+    ) -> Result<CommandStatus> {
+        let system = interaction
+            .get_string_option("system".into())
+            .and_then(GameSystem::from_id);
+
+        let embed = match system {
+            Some(system) => {
+                Storage::with(|connection| GuildSettings::set_system(connection, guild_id, system))
+                    .await?;
+                CreateEmbed::new()
+                    .title(format!(":gear: This server now plays {}", system.name()))
+                    .description("`/skill` and `/op` follow the rules of the system.")
+            }
+            None => {
+                let system = SettingsCommand::system_of(Some(guild_id)).await?;
+                CreateEmbed::new()
+                    .title(format!(":gear: This server plays {}", system.name()))
+                    .description("`/skill` and `/op` follow the rules of the system.")
+            }
+        };
+
+        interaction.send_embed(ctx, embed).await?;
+
+        Ok(CommandStatus::Ok)
+    }
+}
+
+#[naming]
+#[serenity::async_trait]
+impl BotCommand for SettingsCommand {
+    fn create(&self) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description("Changes the settings of this server.")
+            .description_localized("ja", "このサーバーの設定を変更します.")
+            .default_member_permissions(Permissions::MANAGE_GUILD)
+            .contexts(vec![InteractionContext::Guild])
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "system",
+                    "Sets a game system which /skill and /op follow.",
+                )
+                .description_localized("ja", "/skillと/opが従うゲームシステムを設定します.")
+                .add_sub_option(
+                    ALL_SYSTEMS.iter().fold(
+                        CreateCommandOption::new(
+                            CommandOptionType::String,
+                            "system",
+                            "A game system (Leave it empty to show the current one.)",
+                        )
+                        .name_localized("ja", "システム")
+                        .description_localized(
+                            "ja",
+                            "ゲームシステム (空欄にすると現在の設定を表示します.)",
+                        ),
+                        |option, system| option.add_string_choice(system.name(), system.id()),
+                    ),
+                ),
+            )
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let guild_id = match interaction.guild_id {
+            Some(guild_id) => guild_id,
+            None => {
+                return Ok(CommandStatus::Err(
+                    "This command can only be used in a server.".to_string(),
+                ))
+            }
+        };
+
+        match interaction.get_subcommand() {
+            Some("system") => SettingsCommand::execute_system(ctx, interaction, guild_id).await,
+            _ => Ok(CommandStatus::Err("Unknown subcommand.".to_string())),
+        }
+    }
+}
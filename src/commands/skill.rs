@@ -1,12 +1,31 @@
 use anyhow::Result;
-use rand::Rng;
-use serenity::builder::{CreateCommand, CreateCommandOption, CreateEmbed};
-use serenity::model::application::{CommandInteraction, CommandOptionType};
+use serenity::builder::{
+    CreateActionRow, CreateButton, CreateCommand, CreateCommandOption, CreateEmbed,
+    CreateInteractionResponse, CreateInteractionResponseMessage,
+};
+use serenity::model::application::{
+    ButtonStyle, CommandInteraction, CommandOptionType, ComponentInteraction,
+};
+use serenity::model::channel::Embed;
 use serenity::prelude::Context;
 
-use crate::commands::{BotCommand, CommandStatus, InteractionUtil, SendEmbed};
-
-/// A command that does a skill roll. It follows Call of Cthulhu 6th Edition.
+use crate::commands::create_sheet::MAX_NAME_LENGTH;
+use crate::commands::secret::RevealComponent;
+use crate::commands::session::SessionCommand;
+use crate::commands::settings::SettingsCommand;
+use crate::commands::stats::StatsCommand;
+use crate::commands::{BotCommand, BotComponent, CommandStatus, InteractionUtil};
+use crate::dice::DiceSource;
+use crate::rules::{Coc7th, GameSystem, RuleSystem, SkillOutcome, SkillTier};
+use crate::skills::{standard_skill, suggest_skills};
+use crate::storage::character::Character;
+use crate::storage::session::RollRecord;
+use crate::storage::Storage;
+
+/// A maximum number of bonus or penalty dice that can be rolled at once.
+pub const MAX_BONUS_DICE: i32 = 2;
+
+/// A command that does a skill roll. It follows the game system of the server.
 pub struct SkillCommand;
 
 /// A command that does a skill roll. It follows Call of Cthulhu 6th Edition.
@@ -21,160 +40,366 @@ pub struct SkDGCommand;
 /// A command that does a skill roll. It follows the BRP 2023 rule book.
 pub struct SkBRPCommand;
 
-impl SkillCommand {
-    /// Does a skill roll following the rule of Call of Cthulhu 6th Edition.
-    async fn execute_6th(ctx: &Context, interaction: &CommandInteraction) -> Result<CommandStatus> {
-        let chance = interaction.get_int_option("chance".to_string()).unwrap();
+/// A button to push a failed roll of `/sk7`.
+pub struct Sk7PushComponent;
 
-        let comment = interaction
-            .get_string_option("comment".to_string())
-            .unwrap_or("a skill");
+/// A button to spend Luck on a roll of `/sk7`.
+pub struct Sk7LuckComponent;
 
-        let (result, roll) = match rand::thread_rng().gen_range(1..=100) {
-            result if (result == 1 && result <= chance) => (
-                ":star::crown::star: **Critical!!!**",
-                format!("1 <= {}", chance),
-            ),
-            result if result <= 5 && (result <= chance) => {
-                (":crown: **Critical!**", format!("{} <= {}", result, chance))
-            }
-            result if result == 100 && (result > chance) => (
-                ":fire::skull::fire: **Fumble!!!**",
-                format!("100 > {}", chance),
-            ),
-            result if result > 95 && (result > chance) => {
-                (":skull: **Fumble!**", format!("{} > {}", result, chance))
-            }
-            result if result <= chance => (":o: **Success**", format!("{} <= {}", result, chance)),
-            result => (":x: **Failed**", format!("{} > {}", result, chance)),
-        };
+/// A mark put on the pushed result.
+const PUSHED_MARK: &str = ":repeat: Pushed";
 
-        interaction
-            .send_embed(
-                ctx,
-                CreateEmbed::new()
-                    .title(format!("{} uses {}", interaction.get_nickname(), comment))
-                    .field(result, roll, false),
-            )
-            .await?;
+/// A mark put on the result changed by spending Luck.
+const LUCK_MARK: &str = ":four_leaf_clover: Spent";
 
-        Ok(CommandStatus::Ok)
+/// Standard skills which Luck cannot be spent on.
+const NO_LUCK_SKILLS: [&str; 2] = ["SAN", "Luck"];
+
+/// Represents what a skill roll is made for.
+struct SkillCheck {
+    /// A name of the one who rolls the dice.
+    roller: String,
+    chance: i32,
+    /// The standard skill which the designated skill or characteristic stands for.
+    /// If the chance is given directly, it is told by the comment.
+    standard: Option<&'static str>,
+    comment: String,
+}
+
+impl SkillCheck {
+    /// Creates a roll against the chance given directly.
+    fn with_chance(roller: String, chance: i32, comment: Option<&str>) -> Self {
+        SkillCheck {
+            roller,
+            chance,
+            standard: comment.and_then(standard_skill),
+            comment: comment.unwrap_or("a skill").to_string(),
+        }
     }
 
-    /// Does a skill roll following the rule of Call of Cthulhu 7th Edition.
-    async fn execute_7th(ctx: &Context, interaction: &CommandInteraction) -> Result<CommandStatus> {
-        let chance = interaction.get_int_option("chance".to_string()).unwrap();
+    /// Checks whether Luck can be spent on the roll.
+    fn can_spend_luck(&self) -> bool {
+        !self
+            .standard
+            .is_some_and(|standard| NO_LUCK_SKILLS.contains(&standard))
+    }
+
+    /// Creates a record of the roll for the session log.
+    fn record(&self, outcome: &SkillOutcome) -> RollRecord {
+        RollRecord {
+            roller: self.roller.clone(),
+            expression: format!("{} ({})", self.comment, self.chance),
+            dice: outcome.rolled_text.clone(),
+            result: outcome.rolled.to_string(),
+            tier: Some(outcome.tier),
+        }
+    }
+}
 
-        let comment = interaction
-            .get_string_option("comment".to_string())
-            .unwrap_or("a skill");
+impl SkillCommand {
+    /// Creates an option of the skill chance.
+    fn chance_option() -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::Integer,
+            "chance",
+            "A skill chance (Required unless the skill is designated.)",
+        )
+        .description_localized("ja", "技能値 (技能を指定しない場合は必須)")
+    }
 
-        let (result, roll) = match rand::thread_rng().gen_range(1..=100) {
-            result if (result == 1 && result <= chance) => (
-                ":star::crown::star: **Critical!!!**",
-                format!("1 <= {}", chance),
-            ),
-            result if result <= chance / 5 => (
-                ":crown: **Extreme Success!**",
-                format!("{} <= {} / 5", result, chance),
-            ),
-            result if result <= chance / 2 => (
-                ":o: **Hard Success!**",
-                format!("{} <= {} / 2", result, chance),
-            ),
-            result if result == 100 || (result > 95 && chance < 50) => {
-                (":skull: **Fumble!**", format!("{} >= {}", result, chance))
-            }
-            result if result <= chance => (":o: **Success**", format!("{} <= {}", result, chance)),
-            result => (":x: **Failed**", format!("{} > {}", result, chance)),
-        };
+    /// Creates an option of the skill looked up from the active character.
+    fn skill_option() -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::String,
+            "skill",
+            "A skill or a characteristic of your active character (ex. Spot Hidden)",
+        )
+        .name_localized("ja", "技能")
+        .description_localized("ja", "使用中のキャラクターの技能または能力値 (例: 目星)")
+        .max_length(MAX_NAME_LENGTH)
+        .set_autocomplete(true)
+    }
 
-        interaction
-            .send_embed(
-                ctx,
-                CreateEmbed::new()
-                    .title(format!("{} uses {}", interaction.get_nickname(), comment))
-                    .field(result, roll, false),
-            )
-            .await?;
+    /// Creates an option of the bonus or penalty dice of Call of Cthulhu 7th Edition.
+    fn bonus_option() -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::Integer,
+            "bonus",
+            "A number of bonus or penalty dice (ex. +1 for bonus, -1 for penalty)",
+        )
+        .name_localized("ja", "ボーナス")
+        .description_localized(
+            "ja",
+            "ボーナスダイスまたはペナルティダイスの数 (例: +1ならボーナス, -1ならペナルティ)",
+        )
+    }
 
-        Ok(CommandStatus::Ok)
+    /// Creates an option of Luck of Call of Cthulhu 7th Edition.
+    fn luck_option() -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::Integer,
+            "luck",
+            "Current Luck which can be spent on the roll",
+        )
+        .name_localized("ja", "幸運")
+        .description_localized("ja", "ロールに消費できる現在の幸運")
     }
 
-    async fn execute_dg(ctx: &Context, interaction: &CommandInteraction) -> Result<CommandStatus> {
-        let chance = interaction.get_int_option("chance".to_string()).unwrap();
+    /// Creates an option of the comment, which suggests the skills.
+    fn comment_option() -> CreateCommandOption {
+        CreateCommandOption::new(CommandOptionType::String, "comment", "A comment")
+            .description_localized("ja", "ダイスの説明")
+            .set_autocomplete(true)
+    }
 
-        let comment = interaction
-            .get_string_option("comment".to_string())
-            .unwrap_or("a skill");
+    /// Suggests the skills of the active character and the standard ones.
+    pub async fn suggest(interaction: &CommandInteraction) -> Result<Vec<String>> {
+        let typed = match interaction.data.autocomplete() {
+            Some(option) => option.value,
+            None => return Ok(Vec::new()),
+        };
 
-        let (result, roll) = match rand::thread_rng().gen_range(1..=100) {
-            result if (result == 1 && result <= chance) => (
-                ":star::crown::star: **Critical!!!**",
-                format!("1 <= {}", chance),
-            ),
-            result if result <= 5 && (result <= chance) => {
-                (":crown: **Critical!**", format!("{} <= {}", result, chance))
+        let owner_id = interaction.user.id;
+        let character = Storage::with(|connection| Character::active(connection, owner_id)).await?;
+
+        let names = character
+            .iter()
+            .flat_map(|character| character.skills.iter().chain(&character.characteristics))
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+
+        Ok(suggest_skills(&names, typed))
+    }
+
+    /// Splits the title of the result (ex. `Harvey uses Spot Hidden`) into the roller and the comment.
+    fn split_title(embed: &Embed) -> (String, String) {
+        let title = embed.title.as_deref().unwrap_or_default();
+        match title.split_once(" uses ") {
+            Some((roller, comment)) => (roller.to_string(), comment.to_string()),
+            None => (title.to_string(), String::new()),
+        }
+    }
+
+    /// Determines the chance from the options.
+    /// If the skill is designated, the chance is looked up from the active character of the user.
+    async fn check(
+        interaction: &CommandInteraction,
+    ) -> Result<std::result::Result<SkillCheck, String>> {
+        let chance = interaction.get_int_option("chance".to_string());
+        let skill = interaction.get_string_option("skill".to_string());
+        let comment = interaction.get_string_option("comment".to_string());
+
+        let skill = match (chance, skill) {
+            (Some(_), Some(_)) => {
+                return Ok(Err(
+                    "Designate either a chance or a skill, not both.".to_string()
+                ))
             }
-            result if result / 10 == result % 10 && result <= chance => {
-                (":crown: **Critical!**", format!("{} <= {}", result, chance))
+            (None, None) => return Ok(Err("Designate a chance or a skill.".to_string())),
+            (Some(chance), None) => {
+                return Ok(Ok(SkillCheck::with_chance(
+                    interaction.get_nickname(),
+                    chance,
+                    comment,
+                )))
             }
-            result if result == 100 && (result > chance) => (
-                ":fire::skull::fire: **Fumble!!!**",
-                format!("100 > {}", chance),
-            ),
-            result if result > 95 && (result > chance) => {
-                (":skull: **Fumble!**", format!("{} > {}", result, chance))
+            (None, Some(skill)) => skill.trim(),
+        };
+
+        let owner_id = interaction.user.id;
+        let character = Storage::with(|connection| Character::active(connection, owner_id)).await?;
+        let character = match character {
+            Some(character) => character,
+            None => {
+                return Ok(Err(
+                    "You do not have an active character. Create one with /cs.".to_string(),
+                ))
             }
-            result if result <= chance => (":o: **Success**", format!("{} <= {}", result, chance)),
-            result => (":x: **Failed**", format!("{} > {}", result, chance)),
         };
 
-        interaction
-            .send_embed(
-                ctx,
-                CreateEmbed::new()
-                    .title(format!("{} uses {}", interaction.get_nickname(), comment))
-                    .field(result, roll, false),
-            )
-            .await?;
+        // Skills take precedence over characteristics.
+        let entry = character
+            .skills
+            .iter()
+            .chain(character.characteristics.iter())
+            .find(|(name, _)| name.eq_ignore_ascii_case(skill));
+        let (name, chance) = match entry {
+            Some(entry) => entry,
+            None => {
+                return Ok(Err(format!(
+                    "{} does not have \"{}\". Set it with /char set.",
+                    character.name, skill
+                )))
+            }
+        };
+
+        Ok(Ok(SkillCheck {
+            roller: character.name.clone(),
+            chance: *chance,
+            standard: standard_skill(name),
+            comment: comment.unwrap_or(name).to_string(),
+        }))
+    }
+
+    /// Does a skill roll following the rule of the given system.
+    async fn execute_rule(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        system: GameSystem,
+    ) -> Result<CommandStatus> {
+        let check = match SkillCommand::check(interaction).await? {
+            Ok(check) => check,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+
+        let rule = system.rule();
+        let outcome =
+            DiceSource::with_guild(interaction.guild_id, |dice| rule.roll(check.chance, dice))
+                .await?;
+        StatsCommand::record(
+            interaction.guild_id,
+            interaction.user.id,
+            system,
+            &outcome,
+            0,
+            RevealComponent::is_secret(interaction),
+        )
+        .await?;
+
+        RevealComponent::send_roll(
+            ctx,
+            interaction,
+            CreateEmbed::new()
+                .title(format!("{} uses {}", check.roller, check.comment))
+                .field(rule.label(outcome.tier), outcome.describe(), false),
+            Vec::new(),
+            &[check.record(&outcome)],
+        )
+        .await?;
 
         Ok(CommandStatus::Ok)
     }
 
-    async fn execute_brp(ctx: &Context, interaction: &CommandInteraction) -> Result<CommandStatus> {
-        let chance = interaction.get_int_option("chance".to_string()).unwrap();
+    /// Does a skill roll following the rule of Call of Cthulhu 6th Edition.
+    async fn execute_6th(ctx: &Context, interaction: &CommandInteraction) -> Result<CommandStatus> {
+        Self::execute_rule(ctx, interaction, GameSystem::Coc6th).await
+    }
 
-        let comment = interaction
-            .get_string_option("comment".to_string())
-            .unwrap_or("a skill");
+    /// Does a skill roll following the rule of Call of Cthulhu 7th Edition.
+    async fn execute_7th(ctx: &Context, interaction: &CommandInteraction) -> Result<CommandStatus> {
+        let bonus = interaction.get_int_option("bonus".to_string()).unwrap_or(0);
+        let luck = interaction.get_int_option("luck".to_string());
+
+        if !(-MAX_BONUS_DICE..=MAX_BONUS_DICE).contains(&bonus) {
+            return Ok(CommandStatus::Err(format!(
+                "A number of bonus or penalty dice must be {}-{}.",
+                -MAX_BONUS_DICE, MAX_BONUS_DICE
+            )));
+        }
+
+        let check = match SkillCommand::check(interaction).await? {
+            Ok(check) => check,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+        let chance = check.chance;
 
-        let (result, roll) = match rand::thread_rng().gen_range(1..=100) {
-            result if result <= (chance - 1) / 20 + 1 => (
-                ":star::crown::star: **Critical!!!**",
-                format!("{} <= {}", result, chance),
-            ),
-            result if result <= (chance - 1) / 5 + 1 => {
-                (":crown: **Special!**", format!("{} <= {}", result, chance))
+        if let Some(luck) = luck {
+            if !(0..=99).contains(&luck) {
+                return Ok(CommandStatus::Err("Luck must be 0-99.".to_string()));
             }
-            result if result >= i32::min(96 + (chance - 1) / 20, 100) && (result > chance) => {
-                (":skull: **Fumble!**", format!("{} > {}", result, chance))
+            if !check.can_spend_luck() {
+                return Ok(CommandStatus::Err(
+                    "Luck cannot be spent on SAN or Luck rolls.".to_string(),
+                ));
             }
-            result if result <= chance => (":o: **Success**", format!("{} <= {}", result, chance)),
-            result => (":x: **Failed**", format!("{} > {}", result, chance)),
+        }
+
+        let outcome = DiceSource::with_guild(interaction.guild_id, |dice| {
+            Coc7th.roll_with_bonus(chance, bonus, dice)
+        })
+        .await?;
+        StatsCommand::record(
+            interaction.guild_id,
+            interaction.user.id,
+            GameSystem::Coc7th,
+            &outcome,
+            bonus,
+            RevealComponent::is_secret(interaction),
+        )
+        .await?;
+
+        let embed = CreateEmbed::new()
+            .title(format!("{} uses {}", check.roller, check.comment))
+            .field(Coc7th.label(outcome.tier), outcome.describe(), false);
+
+        let mut buttons = Vec::new();
+
+        // A failed roll can be pushed once.
+        if outcome.tier == SkillTier::Failure {
+            let custom_id = Sk7PushComponent.custom_id(&[
+                interaction.user.id.to_string(),
+                chance.to_string(),
+                bonus.to_string(),
+            ]);
+            buttons.push(
+                CreateButton::new(custom_id)
+                    .label("Push")
+                    .emoji('🔁')
+                    .style(ButtonStyle::Danger),
+            );
+        }
+
+        // Luck can be spent to reach a higher tier.
+        if let Some(luck) = luck {
+            for target in Coc7th.luck_targets(outcome.tier) {
+                let max_value = Coc7th.max_value(chance, *target);
+                let cost = outcome.rolled - max_value;
+                if cost > luck || max_value < 1 {
+                    continue;
+                }
+                let custom_id = Sk7LuckComponent.custom_id(&[
+                    interaction.user.id.to_string(),
+                    chance.to_string(),
+                    outcome.rolled.to_string(),
+                    luck.to_string(),
+                    target.id().to_string(),
+                ]);
+                buttons.push(
+                    CreateButton::new(custom_id)
+                        .label(format!("Spend {} Luck ({})", cost, target.name()))
+                        .emoji('🍀')
+                        .style(ButtonStyle::Success),
+                );
+            }
+        }
+
+        let components = if buttons.is_empty() {
+            Vec::new()
+        } else {
+            vec![CreateActionRow::Buttons(buttons)]
         };
 
-        interaction
-            .send_embed(
-                ctx,
-                CreateEmbed::new()
-                    .title(format!("{} uses {}", interaction.get_nickname(), comment))
-                    .field(result, roll, false),
-            )
-            .await?;
+        RevealComponent::send_roll(
+            ctx,
+            interaction,
+            embed,
+            components,
+            &[check.record(&outcome)],
+        )
+        .await?;
 
         Ok(CommandStatus::Ok)
     }
+
+    /// Does a skill roll following the rule of Delta Green.
+    async fn execute_dg(ctx: &Context, interaction: &CommandInteraction) -> Result<CommandStatus> {
+        Self::execute_rule(ctx, interaction, GameSystem::DeltaGreen).await
+    }
+
+    /// Does a skill roll following the rule of the BRP 2023 rule book.
+    async fn execute_brp(ctx: &Context, interaction: &CommandInteraction) -> Result<CommandStatus> {
+        Self::execute_rule(ctx, interaction, GameSystem::Brp).await
+    }
 }
 
 #[naming]
@@ -182,20 +407,25 @@ impl SkillCommand {
 impl BotCommand for SkillCommand {
     fn create(&self) -> CreateCommand {
         CreateCommand::new(self.name())
-            .description("Does a skill roll. This is an alias for `/sk6` (The CoC 6th Edition).")
+            .description("Does a skill roll following the game system of this server (default: CoC 6th Edition).")
             .description_localized(
                 "ja",
-                "第6版のルールに基づいて技能ロールを行います. `/sk6`と全く同じです.",
-            )
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::Integer, "chance", "A skill chance")
-                    .description_localized("ja", "技能値")
-                    .required(true),
-            )
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "comment", "A comment")
-                    .description_localized("ja", "ダイスの説明"),
+                "このサーバーのゲームシステムに基づいて技能ロールを行います (既定: 第6版).",
             )
+            .add_option(SkillCommand::chance_option())
+            .add_option(SkillCommand::skill_option())
+            .add_option(SkillCommand::bonus_option())
+            .add_option(SkillCommand::luck_option())
+            .add_option(SkillCommand::comment_option())
+            .add_option(RevealComponent::secret_option())
+    }
+
+    async fn autocomplete(
+        &self,
+        _ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<Vec<String>> {
+        SkillCommand::suggest(interaction).await
     }
 
     async fn execute(
@@ -203,7 +433,21 @@ impl BotCommand for SkillCommand {
         ctx: &Context,
         interaction: &CommandInteraction,
     ) -> Result<CommandStatus> {
-        Self::execute_6th(ctx, interaction).await
+        let system = SettingsCommand::system_of(interaction.guild_id).await?;
+        if system == GameSystem::Coc7th {
+            return Self::execute_7th(ctx, interaction).await;
+        }
+
+        let bonus = interaction.get_int_option("bonus".to_string());
+        let luck = interaction.get_int_option("luck".to_string());
+        if bonus.is_some() || luck.is_some() {
+            return Ok(CommandStatus::Err(format!(
+                "Bonus dice and Luck are not available in {}.",
+                system.name()
+            )));
+        }
+
+        Self::execute_rule(ctx, interaction, system).await
     }
 }
 
@@ -214,15 +458,18 @@ impl BotCommand for Sk6Command {
         CreateCommand::new(self.name())
             .description("Does a skill roll following the Call of Cthulhu 6th Edition.")
             .description_localized("ja", "第6版のルールに基づいて技能ロールを行います.")
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::Integer, "chance", "A skill chance")
-                    .description_localized("ja", "技能値")
-                    .required(true),
-            )
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "comment", "A comment")
-                    .description_localized("ja", "ダイスの説明"),
-            )
+            .add_option(SkillCommand::chance_option())
+            .add_option(SkillCommand::skill_option())
+            .add_option(SkillCommand::comment_option())
+            .add_option(RevealComponent::secret_option())
+    }
+
+    async fn autocomplete(
+        &self,
+        _ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<Vec<String>> {
+        SkillCommand::suggest(interaction).await
     }
 
     async fn execute(
@@ -241,15 +488,20 @@ impl BotCommand for Sk7Command {
         CreateCommand::new(self.name())
             .description("Does a skill roll following the Call of Cthulhu 7th Edition.")
             .description_localized("ja", "第7版のルールに基づいて技能ロールを行います.")
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::Integer, "chance", "A skill chance")
-                    .description_localized("ja", "技能値")
-                    .required(true),
-            )
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "comment", "A comment")
-                    .description_localized("ja", "ダイスの説明"),
-            )
+            .add_option(SkillCommand::chance_option())
+            .add_option(SkillCommand::skill_option())
+            .add_option(SkillCommand::bonus_option())
+            .add_option(SkillCommand::luck_option())
+            .add_option(SkillCommand::comment_option())
+            .add_option(RevealComponent::secret_option())
+    }
+
+    async fn autocomplete(
+        &self,
+        _ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<Vec<String>> {
+        SkillCommand::suggest(interaction).await
     }
 
     async fn execute(
@@ -268,15 +520,18 @@ impl BotCommand for SkDGCommand {
         CreateCommand::new(self.name())
             .description("Does a skill roll following the Delta Green.")
             .description_localized("ja", "Delta Greenのルールに基づいて技能ロールを行います.")
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::Integer, "chance", "A skill chance")
-                    .description_localized("ja", "技能値")
-                    .required(true),
-            )
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "comment", "A comment")
-                    .description_localized("ja", "ダイスの説明"),
-            )
+            .add_option(SkillCommand::chance_option())
+            .add_option(SkillCommand::skill_option())
+            .add_option(SkillCommand::comment_option())
+            .add_option(RevealComponent::secret_option())
+    }
+
+    async fn autocomplete(
+        &self,
+        _ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<Vec<String>> {
+        SkillCommand::suggest(interaction).await
     }
 
     async fn execute(
@@ -295,15 +550,18 @@ impl BotCommand for SkBRPCommand {
         CreateCommand::new(self.name())
             .description("Does a skill roll following the BRP 2023.")
             .description_localized("ja", "BRP 2023のルールに基づいて技能ロールを行います.")
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::Integer, "chance", "A skill chance")
-                    .description_localized("ja", "技能値")
-                    .required(true),
-            )
-            .add_option(
-                CreateCommandOption::new(CommandOptionType::String, "comment", "A comment")
-                    .description_localized("ja", "ダイスの説明"),
-            )
+            .add_option(SkillCommand::chance_option())
+            .add_option(SkillCommand::skill_option())
+            .add_option(SkillCommand::comment_option())
+            .add_option(RevealComponent::secret_option())
+    }
+
+    async fn autocomplete(
+        &self,
+        _ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<Vec<String>> {
+        SkillCommand::suggest(interaction).await
     }
 
     async fn execute(
@@ -314,3 +572,233 @@ impl BotCommand for SkBRPCommand {
         SkillCommand::execute_brp(ctx, interaction).await
     }
 }
+
+#[serenity::async_trait]
+impl BotComponent for Sk7PushComponent {
+    fn name(&self) -> &str {
+        "sk7push"
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &ComponentInteraction,
+        args: &[&str],
+    ) -> Result<CommandStatus> {
+        let (user_id, chance, bonus) = match args {
+            [user_id, chance, bonus] => (*user_id, chance.parse()?, bonus.parse()?),
+            _ => return Ok(CommandStatus::Err("The button is broken.".to_string())),
+        };
+
+        if interaction.user.id.to_string() != user_id {
+            return Ok(CommandStatus::Err(
+                "Only the user who rolled the dice can push it.".to_string(),
+            ));
+        }
+
+        let embed = match interaction.message.embeds.first() {
+            Some(embed) => embed,
+            None => return Ok(CommandStatus::Err("The result is missing.".to_string())),
+        };
+        if embed
+            .fields
+            .iter()
+            .any(|field| field.name.starts_with(PUSHED_MARK))
+        {
+            return Ok(CommandStatus::Err(
+                "This roll has already been pushed.".to_string(),
+            ));
+        }
+        if embed
+            .fields
+            .iter()
+            .any(|field| field.name.starts_with(LUCK_MARK))
+        {
+            return Ok(CommandStatus::Err(
+                "Luck has already been spent on this roll.".to_string(),
+            ));
+        }
+
+        let outcome = DiceSource::with_guild(interaction.guild_id, |dice| {
+            Coc7th.roll_with_bonus(chance, bonus, dice)
+        })
+        .await?;
+        let (roller, comment) = SkillCommand::split_title(embed);
+        let record = RollRecord {
+            roller,
+            expression: format!("{} ({}), pushed", comment, chance),
+            dice: outcome.rolled_text.clone(),
+            result: outcome.rolled.to_string(),
+            tier: Some(outcome.tier),
+        };
+
+        let embed = CreateEmbed::from(embed.clone()).field(
+            format!("{} {}", PUSHED_MARK, Coc7th.label(outcome.tier)),
+            outcome.describe(),
+            false,
+        );
+        let components = RevealComponent::keep(&interaction.message, &embed).await?;
+
+        // The Reveal button is kept only on secret rolls.
+        let secret = !components.is_empty();
+        StatsCommand::record(
+            interaction.guild_id,
+            interaction.user.id,
+            GameSystem::Coc7th,
+            &outcome,
+            bonus,
+            secret,
+        )
+        .await?;
+        SessionCommand::record(
+            interaction.channel_id,
+            interaction.user.id,
+            self.name(),
+            secret,
+            &[record],
+        )
+        .await?;
+
+        interaction
+            .create_response(
+                &ctx,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::default()
+                        .embed(embed)
+                        .components(components),
+                ),
+            )
+            .await?;
+
+        Ok(CommandStatus::Ok)
+    }
+}
+
+#[serenity::async_trait]
+impl BotComponent for Sk7LuckComponent {
+    fn name(&self) -> &str {
+        "sk7luck"
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &ComponentInteraction,
+        args: &[&str],
+    ) -> Result<CommandStatus> {
+        let (user_id, chance, rolled, luck, target): (_, i32, i32, i32, _) = match args {
+            [user_id, chance, rolled, luck, target] => (
+                *user_id,
+                chance.parse()?,
+                rolled.parse()?,
+                luck.parse()?,
+                SkillTier::from_id(target),
+            ),
+            _ => return Ok(CommandStatus::Err("The button is broken.".to_string())),
+        };
+        let target = match target {
+            Some(target) => target,
+            None => return Ok(CommandStatus::Err("The button is broken.".to_string())),
+        };
+
+        if interaction.user.id.to_string() != user_id {
+            return Ok(CommandStatus::Err(
+                "Only the user who rolled the dice can spend Luck.".to_string(),
+            ));
+        }
+
+        let embed = match interaction.message.embeds.first() {
+            Some(embed) => embed,
+            None => return Ok(CommandStatus::Err("The result is missing.".to_string())),
+        };
+        if embed
+            .fields
+            .iter()
+            .any(|field| field.name.starts_with(PUSHED_MARK))
+        {
+            return Ok(CommandStatus::Err(
+                "Luck cannot be spent on pushed rolls.".to_string(),
+            ));
+        }
+        if embed
+            .fields
+            .iter()
+            .any(|field| field.name.starts_with(LUCK_MARK))
+        {
+            return Ok(CommandStatus::Err(
+                "Luck has already been spent on this roll.".to_string(),
+            ));
+        }
+
+        let threshold = Coc7th.max_value(chance, target);
+        let cost = rolled - threshold;
+        if cost > luck {
+            return Ok(CommandStatus::Err("Not enough Luck.".to_string()));
+        }
+
+        let (roller, comment) = SkillCommand::split_title(embed);
+        let record = RollRecord {
+            roller,
+            expression: format!("{} ({}), {} Luck spent", comment, chance, cost),
+            dice: format!("{} - {}", rolled, cost),
+            result: threshold.to_string(),
+            tier: Some(target),
+        };
+
+        let embed = CreateEmbed::from(embed.clone()).field(
+            format!("{} {} Luck {}", LUCK_MARK, cost, Coc7th.label(target)),
+            format!(
+                "{} - {} = {} (Luck: {} -> {})",
+                rolled,
+                cost,
+                threshold,
+                luck,
+                luck - cost
+            ),
+            false,
+        );
+        let components = RevealComponent::keep(&interaction.message, &embed).await?;
+
+        SessionCommand::record(
+            interaction.channel_id,
+            interaction.user.id,
+            self.name(),
+            !components.is_empty(),
+            &[record],
+        )
+        .await?;
+
+        interaction
+            .create_response(
+                &ctx,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::default()
+                        .embed(embed)
+                        .components(components),
+                ),
+            )
+            .await?;
+
+        Ok(CommandStatus::Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn luck_cannot_be_spent_on_san_or_luck() {
+        let check = |comment| SkillCheck::with_chance("Harvey".to_string(), 50, comment);
+
+        assert!(!check(Some("SAN")).can_spend_luck());
+        assert!(!check(Some("san check")).can_spend_luck());
+        assert!(!check(Some("正気度ロール")).can_spend_luck());
+        assert!(!check(Some("幸運")).can_spend_luck());
+        assert!(!check(Some("Luck")).can_spend_luck());
+
+        assert!(check(Some("Spot Hidden")).can_spend_luck());
+        assert!(check(Some("a door")).can_spend_luck());
+        assert!(check(None).can_spend_luck());
+    }
+}
@@ -0,0 +1,266 @@
+use anyhow::Result;
+use serenity::builder::{CreateCommand, CreateCommandOption, CreateEmbed};
+use serenity::model::application::{CommandInteraction, CommandOptionType};
+use serenity::model::id::{GuildId, UserId};
+use serenity::prelude::Context;
+
+use crate::commands::{BotCommand, CommandStatus, InteractionUtil, SendEmbed};
+use crate::fairness::{self, ChiSquare, BUCKETS};
+use crate::rules::{GameSystem, SkillOutcome, ALL_SYSTEMS};
+use crate::storage::stats::RollStats;
+use crate::storage::Storage;
+
+/// The width of the longest bar of the histogram.
+const HISTOGRAM_WIDTH: u32 = 20;
+
+/// A p-value under which the dice are reported as suspicious.
+const SIGNIFICANCE_LEVEL: f64 = 0.01;
+
+/// A command that shows the statistics of the skill rolls.
+pub struct StatsCommand;
+
+impl StatsCommand {
+    /// Records the outcome of a skill roll for the statistics.
+    /// A secret roll is not recorded since the statistics before and after it would tell its result.
+    pub async fn record(
+        guild_id: Option<GuildId>,
+        user_id: UserId,
+        system: GameSystem,
+        outcome: &SkillOutcome,
+        bonus: i32,
+        secret: bool,
+    ) -> Result<()> {
+        if secret {
+            return Ok(());
+        }
+        Storage::with(|connection| {
+            RollStats::record(connection, guild_id, user_id, system, outcome, bonus)
+        })
+        .await
+    }
+
+    /// Describes the number of the rolls for each tier of the system.
+    /// Returns `None` if nothing is rolled in the system.
+    fn tiers_text(stats: &RollStats, system: GameSystem) -> Option<String> {
+        let mut counts: Vec<(&str, u32)> = Vec::new();
+        for (_, tier, count) in stats.tiers.iter().filter(|(s, _, _)| *s == system) {
+            // The great critical and fumble are counted as the ordinary ones.
+            match counts.iter_mut().find(|(name, _)| *name == tier.name()) {
+                Some((_, total)) => *total += count,
+                None => counts.push((tier.name(), *count)),
+            }
+        }
+        if counts.is_empty() {
+            return None;
+        }
+
+        let total = counts.iter().map(|(_, count)| count).sum::<u32>();
+        let counts = counts
+            .iter()
+            .map(|(name, count)| format!("{} {}", name, count))
+            .collect::<Vec<_>>()
+            .join(" / ");
+        Some(format!("{} ({} rolls)", counts, total))
+    }
+
+    /// Draws the histogram of the d100 results as a text.
+    fn histogram_text(buckets: &[u32; BUCKETS]) -> String {
+        let max = buckets.iter().copied().max().unwrap_or(0).max(1);
+        let lines = buckets
+            .iter()
+            .enumerate()
+            .map(|(i, count)| {
+                let width = (count * HISTOGRAM_WIDTH).div_ceil(max);
+                format!(
+                    "{:>3}-{:<3} {:<width$} {}",
+                    i * 10 + 1,
+                    i * 10 + 10,
+                    "#".repeat(width as usize),
+                    count,
+                    width = HISTOGRAM_WIDTH as usize
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!("```\n{}\n```", lines)
+    }
+
+    /// Explains the result of the chi-square test.
+    fn fairness_text(result: Option<ChiSquare>) -> String {
+        match result {
+            Some(result) => format!(
+                "χ² = {:.2} (df = {}), p = {:.3}\n{}",
+                result.statistic,
+                result.degrees_of_freedom,
+                result.p_value,
+                if result.p_value < SIGNIFICANCE_LEVEL {
+                    ":warning: The results are unlikely for fair dice."
+                } else {
+                    ":white_check_mark: The results fit fair dice."
+                }
+            ),
+            None => "Not enough rolls to test yet. Roll at least 50 times.".to_string(),
+        }
+    }
+}
+
+#[naming]
+#[serenity::async_trait]
+impl BotCommand for StatsCommand {
+    fn create(&self) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description(
+                "Shows the statistics of the skill rolls and tests whether the dice are fair.",
+            )
+            .description_localized(
+                "ja",
+                "技能ロールの統計を表示し, ダイスの公平性を検定します.",
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::User,
+                    "user",
+                    "A user to show (default: you)",
+                )
+                .name_localized("ja", "ユーザー")
+                .description_localized("ja", "表示するユーザー (既定: 自分)"),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "everyone",
+                    "Shows the rolls of everyone in this server (default: false)",
+                )
+                .name_localized("ja", "全員")
+                .description_localized(
+                    "ja",
+                    "このサーバーの全員のロールを表示します (既定: false)",
+                ),
+            )
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let everyone = interaction.get_bool_option("everyone".into()) == Some(true);
+        let user = interaction.get_user_option("user".into());
+
+        let (user_id, title) = match (everyone, user) {
+            (true, Some(_)) => {
+                return Ok(CommandStatus::Err(
+                    "Designate either a user or everyone, not both.".to_string(),
+                ))
+            }
+            (true, None) => (None, "Rolls of everyone".to_string()),
+            (false, Some(user)) => (Some(user.id), format!("{}'s rolls", user.display_name())),
+            (false, None) => (
+                Some(interaction.user.id),
+                format!("{}'s rolls", interaction.get_nickname()),
+            ),
+        };
+
+        let guild_id = interaction.guild_id;
+        let stats =
+            Storage::with(|connection| RollStats::load(connection, guild_id, user_id)).await?;
+
+        let mut embed = CreateEmbed::new().title(format!(":bar_chart: {}", title));
+
+        let systems = ALL_SYSTEMS
+            .iter()
+            .filter_map(|system| Some((system, StatsCommand::tiers_text(&stats, *system)?)))
+            .collect::<Vec<_>>();
+        if systems.is_empty() {
+            embed = embed.description("No skill rolls have been recorded yet.");
+        }
+        for (system, text) in systems {
+            embed = embed.field(system.name(), text, false);
+        }
+
+        if !stats.d100.is_empty() {
+            let average = stats.d100.iter().sum::<i32>() as f64 / stats.d100.len() as f64;
+            let buckets = fairness::histogram(&stats.d100);
+
+            embed = embed
+                .field(
+                    ":game_die: Average d100",
+                    format!(
+                        "{:.1} (fair dice: 50.5, {} rolls without bonus or penalty dice)",
+                        average,
+                        stats.d100.len()
+                    ),
+                    false,
+                )
+                .field(
+                    ":bar_chart: Histogram",
+                    StatsCommand::histogram_text(&buckets),
+                    false,
+                )
+                .field(
+                    ":scales: Chi-square test",
+                    StatsCommand::fairness_text(fairness::chi_square(&buckets)),
+                    false,
+                );
+        }
+
+        interaction.send_embed(ctx, embed).await?;
+
+        Ok(CommandStatus::Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::SkillTier;
+
+    #[test]
+    fn tiers_are_merged() {
+        let stats = RollStats {
+            tiers: vec![
+                (GameSystem::Coc6th, SkillTier::GreatCritical, 1),
+                (GameSystem::Coc6th, SkillTier::Critical, 2),
+                (GameSystem::Coc6th, SkillTier::Failure, 4),
+                (GameSystem::Coc7th, SkillTier::Hard, 1),
+            ],
+            d100: Vec::new(),
+        };
+
+        assert_eq!(
+            StatsCommand::tiers_text(&stats, GameSystem::Coc6th).as_deref(),
+            Some("Critical 3 / Failure 4 (7 rolls)")
+        );
+        assert!(StatsCommand::tiers_text(&stats, GameSystem::Brp).is_none());
+    }
+
+    #[test]
+    fn secret_rolls_are_not_recorded() {
+        // The storage is not initialized in the tests, so recording the roll would panic.
+        let outcome = GameSystem::Coc6th.rule().evaluate(50, 70, "70".to_string());
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime
+            .block_on(StatsCommand::record(
+                Some(GuildId::new(1)),
+                UserId::new(1),
+                GameSystem::Coc6th,
+                &outcome,
+                0,
+                true,
+            ))
+            .unwrap();
+    }
+
+    #[test]
+    fn histogram_is_scaled() {
+        let text = StatsCommand::histogram_text(&[10, 5, 0, 0, 0, 0, 0, 0, 0, 1]);
+        let lines = text.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines[1], format!("  1-10  {} 10", "#".repeat(20)));
+        assert_eq!(lines[2], format!(" 11-20  {:<20} 5", "#".repeat(10)));
+        assert_eq!(lines[3], format!(" 21-30  {:<20} 0", ""));
+        assert_eq!(lines[10], format!(" 91-100 {:<20} 1", "#".repeat(2)));
+    }
+}
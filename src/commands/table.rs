@@ -0,0 +1,382 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use serenity::builder::{CreateCommand, CreateCommandOption, CreateEmbed};
+use serenity::model::application::{CommandInteraction, CommandOptionType, InteractionContext};
+use serenity::model::id::{GuildId, UserId};
+use serenity::prelude::Context;
+
+use crate::commands::roll::RollCommand;
+use crate::commands::session::SessionCommand;
+use crate::commands::{BotCommand, CommandStatus, InteractionUtil, SendEmbed};
+use crate::dice::DiceSource;
+use crate::random_table::{self, TableFormat};
+use crate::storage::random_table::StoredTable;
+use crate::storage::session::RollRecord;
+use crate::storage::Storage;
+
+/// The maximum length of a name of a table.
+const MAX_TABLE_NAME_LENGTH: u16 = 32;
+
+/// The maximum number of tables in a server.
+const MAX_TABLES: usize = 100;
+
+/// The maximum size of a file to upload.
+const MAX_UPLOAD_SIZE: u32 = 256 * 1024;
+
+/// The maximum length of a description of an embed.
+const MAX_DESCRIPTION_LENGTH: usize = 4096;
+
+/// The maximum length of a value of an embed field.
+const MAX_FIELD_LENGTH: usize = 1024;
+
+/// A command that manages and rolls the random tables of the server.
+pub struct TableCommand;
+
+impl TableCommand {
+    /// Creates an option to designate a table.
+    fn name_option(description: &str, description_ja: &str) -> CreateCommandOption {
+        CreateCommandOption::new(CommandOptionType::String, "name", description)
+            .name_localized("ja", "名前")
+            .description_localized("ja", description_ja)
+            .max_length(MAX_TABLE_NAME_LENGTH)
+            .required(true)
+    }
+
+    /// Checks whether the user can replace or delete the table uploaded by `owner_id`.
+    /// The ones who can manage the server can change any table.
+    fn can_change(interaction: &CommandInteraction, owner_id: UserId) -> bool {
+        owner_id == interaction.user.id
+            || interaction
+                .member
+                .as_ref()
+                .and_then(|member| member.permissions)
+                .is_some_and(|permissions| permissions.manage_guild())
+    }
+
+    /// Cuts the text so that it fits in the limit.
+    fn truncate(text: &str, limit: usize) -> String {
+        if text.chars().count() <= limit {
+            return text.to_string();
+        }
+        let mut truncated = text.chars().take(limit - 1).collect::<String>();
+        truncated.push('…');
+        truncated
+    }
+
+    async fn execute_upload(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        guild_id: GuildId,
+    ) -> Result<CommandStatus> {
+        let name = interaction.get_string_option("name".into()).unwrap().trim();
+        if name.is_empty() || name.contains(['[', ']']) {
+            return Ok(CommandStatus::Err(
+                "The name must not be empty nor contain `[` or `]`.".to_string(),
+            ));
+        }
+
+        let file = interaction.get_attachment_option("file".into()).unwrap();
+        if file.size > MAX_UPLOAD_SIZE {
+            return Ok(CommandStatus::Err(format!(
+                "The file must be smaller than {} KB.",
+                MAX_UPLOAD_SIZE / 1024
+            )));
+        }
+        let source = match String::from_utf8(file.download().await?) {
+            Ok(source) => source,
+            Err(_) => {
+                return Ok(CommandStatus::Err(
+                    "The file must be encoded in UTF-8.".to_string(),
+                ))
+            }
+        };
+
+        let table = match random_table::parse(&source, TableFormat::from_file_name(&file.filename))
+        {
+            Ok(table) => table,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+
+        let tables = Storage::with(|connection| StoredTable::list(connection, guild_id)).await?;
+        match tables
+            .iter()
+            .find(|stored| stored.name.eq_ignore_ascii_case(name))
+        {
+            Some(stored) if !TableCommand::can_change(interaction, stored.owner_id) => {
+                return Ok(CommandStatus::Err(format!(
+                    "\"{}\" was uploaded by someone else. Only they or the managers of the server can replace it.",
+                    stored.name
+                )))
+            }
+            None if tables.len() >= MAX_TABLES => {
+                return Ok(CommandStatus::Err(format!(
+                    "A server can have up to {} tables. Delete some with /table delete.",
+                    MAX_TABLES
+                )))
+            }
+            _ => (),
+        }
+
+        let stored = StoredTable {
+            guild_id,
+            name: name.to_string(),
+            owner_id: interaction.user.id,
+            table,
+        };
+        Storage::with(|connection| stored.save(connection)).await?;
+
+        let embed = CreateEmbed::new()
+            .title(format!(":inbox_tray: Saved the table \"{}\"", name))
+            .description(format!(
+                "{} rows rolled with `{}`. Roll it with `/table roll name:{}`.",
+                stored.table.rows.len(),
+                stored.table.dice,
+                name
+            ));
+
+        interaction.send_embed(ctx, embed).await?;
+
+        Ok(CommandStatus::Ok)
+    }
+
+    async fn execute_roll(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        guild_id: GuildId,
+    ) -> Result<CommandStatus> {
+        let name = interaction.get_string_option("name".into()).unwrap().trim();
+
+        // Load all of the tables since the rows may refer to the others.
+        let tables = Storage::with(|connection| StoredTable::list(connection, guild_id))
+            .await?
+            .into_iter()
+            .map(|stored| (stored.name.to_lowercase(), stored.table))
+            .collect::<HashMap<_, _>>();
+        let table = match tables.get(&name.to_lowercase()) {
+            Some(table) => table,
+            None => {
+                return Ok(CommandStatus::Err(format!(
+                    "This server does not have a table named \"{}\".",
+                    name
+                )))
+            }
+        };
+
+        let rolled = DiceSource::with_guild(interaction.guild_id, |dice| {
+            table.roll(&tables, &mut |expr| {
+                RollCommand::evaluate_dice_expr(expr, dice)
+            })
+        })
+        .await?;
+        let rolled = match rolled {
+            Ok(rolled) => rolled,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+
+        let mut embed = CreateEmbed::new()
+            .title(format!("{} rolls on {}", interaction.get_nickname(), name))
+            .description(TableCommand::truncate(&rolled.text, MAX_DESCRIPTION_LENGTH))
+            .field(
+                format!(":game_die: {}", rolled.rolled),
+                TableCommand::truncate(&rolled.dice, MAX_FIELD_LENGTH),
+                false,
+            );
+        if !rolled.details.is_empty() {
+            embed = embed.field(
+                "Details",
+                TableCommand::truncate(&rolled.details.join("\n"), MAX_FIELD_LENGTH),
+                false,
+            );
+        }
+
+        let record = RollRecord {
+            roller: interaction.get_nickname(),
+            expression: format!("{} ({})", name, table.dice),
+            dice: rolled.dice.clone(),
+            result: format!("{}: {}", rolled.rolled, rolled.text),
+            tier: None,
+        };
+        SessionCommand::record_command(interaction, &[record]).await?;
+
+        interaction.send_embed(ctx, embed).await?;
+
+        Ok(CommandStatus::Ok)
+    }
+
+    async fn execute_list(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        guild_id: GuildId,
+    ) -> Result<CommandStatus> {
+        let tables = Storage::with(|connection| StoredTable::list(connection, guild_id)).await?;
+        if tables.is_empty() {
+            return Ok(CommandStatus::Err(
+                "This server does not have any tables. Upload one with /table upload.".to_string(),
+            ));
+        }
+
+        let names = tables
+            .iter()
+            .map(|stored| {
+                format!(
+                    ":scroll: **{}** ({} rows, `{}`)",
+                    stored.name,
+                    stored.table.rows.len(),
+                    stored.table.dice
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let embed = CreateEmbed::new()
+            .title("Tables of this server")
+            .description(names);
+
+        interaction.send_embed(ctx, embed).await?;
+
+        Ok(CommandStatus::Ok)
+    }
+
+    async fn execute_delete(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+        guild_id: GuildId,
+    ) -> Result<CommandStatus> {
+        let name = interaction.get_string_option("name".into()).unwrap().trim();
+
+        let stored =
+            Storage::with(|connection| StoredTable::find(connection, guild_id, name)).await?;
+        let stored = match stored {
+            Some(stored) => stored,
+            None => {
+                return Ok(CommandStatus::Err(format!(
+                    "This server does not have a table named \"{}\".",
+                    name
+                )))
+            }
+        };
+        if !TableCommand::can_change(interaction, stored.owner_id) {
+            return Ok(CommandStatus::Err(format!(
+                "\"{}\" was uploaded by someone else. Only they or the managers of the server can delete it.",
+                stored.name
+            )));
+        }
+
+        Storage::with(|connection| StoredTable::delete(connection, guild_id, name)).await?;
+
+        let embed = CreateEmbed::new().title(format!(
+            ":wastebasket: Deleted the table \"{}\"",
+            stored.name
+        ));
+
+        interaction.send_embed(ctx, embed).await?;
+
+        Ok(CommandStatus::Ok)
+    }
+}
+
+#[naming]
+#[serenity::async_trait]
+impl BotCommand for TableCommand {
+    fn create(&self) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description("Manages and rolls the random tables of this server.")
+            .description_localized("ja", "このサーバーのランダム表を管理して振ります.")
+            .contexts(vec![InteractionContext::Guild])
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "upload",
+                    "Saves a table written in a JSON, CSV or text file.",
+                )
+                .description_localized(
+                    "ja",
+                    "JSON, CSV またはテキストファイルに書かれた表を保存します.",
+                )
+                .add_sub_option(TableCommand::name_option(
+                    "A name to save the table under",
+                    "表を保存する名前",
+                ))
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Attachment,
+                        "file",
+                        "A file of the table (ex. a line `01-15: Cultist` for each row)",
+                    )
+                    .name_localized("ja", "ファイル")
+                    .description_localized("ja", "表のファイル (例: 1行ごとに `01-15: 狂信者`)")
+                    .required(true),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "roll", "Rolls a table.")
+                    .description_localized("ja", "表を振ります.")
+                    .add_sub_option(
+                        TableCommand::name_option("A name of the table", "表の名前")
+                            .set_autocomplete(true),
+                    ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "list",
+                    "Lists the tables of this server.",
+                )
+                .description_localized("ja", "このサーバーの表を一覧表示します."),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "delete",
+                    "Deletes a table.",
+                )
+                .description_localized("ja", "表を削除します.")
+                .add_sub_option(
+                    TableCommand::name_option("A name of the table", "表の名前")
+                        .set_autocomplete(true),
+                ),
+            )
+    }
+
+    async fn autocomplete(
+        &self,
+        _ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<Vec<String>> {
+        let (guild_id, typed) = match (interaction.guild_id, interaction.data.autocomplete()) {
+            (Some(guild_id), Some(option)) => (guild_id, option.value.to_lowercase()),
+            _ => return Ok(Vec::new()),
+        };
+
+        let tables = Storage::with(|connection| StoredTable::list(connection, guild_id)).await?;
+        Ok(tables
+            .into_iter()
+            .map(|stored| stored.name)
+            .filter(|name| name.to_lowercase().contains(&typed))
+            .collect())
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let guild_id = match interaction.guild_id {
+            Some(guild_id) => guild_id,
+            None => {
+                return Ok(CommandStatus::Err(
+                    "This command can only be used in a server.".to_string(),
+                ))
+            }
+        };
+
+        match interaction.get_subcommand() {
+            Some("upload") => TableCommand::execute_upload(ctx, interaction, guild_id).await,
+            Some("roll") => TableCommand::execute_roll(ctx, interaction, guild_id).await,
+            Some("list") => TableCommand::execute_list(ctx, interaction, guild_id).await,
+            Some("delete") => TableCommand::execute_delete(ctx, interaction, guild_id).await,
+            _ => Ok(CommandStatus::Err("Unknown subcommand.".to_string())),
+        }
+    }
+}
@@ -0,0 +1,403 @@
+use anyhow::Result;
+use serenity::builder::{CreateCommand, CreateCommandOption, CreateEmbed};
+use serenity::model::application::{CommandInteraction, CommandOptionType};
+use serenity::prelude::Context;
+
+use crate::commands::character::CharCommand;
+use crate::commands::roll::RollCommand;
+use crate::commands::session::SessionCommand;
+use crate::commands::{BotCommand, CommandStatus, InteractionUtil, SendEmbed};
+use crate::dice::DiceSource;
+use crate::storage::character::Character;
+use crate::storage::session::RollRecord;
+use crate::storage::Storage;
+use crate::vitals::{Condition, Vitals, MAX_SAN};
+
+/// The maximum length of a dice expression.
+const MAX_EXPR_LENGTH: u16 = 100;
+
+/// A command that tracks HP of a character.
+pub struct HpCommand;
+
+/// A command that tracks MP of a character.
+pub struct MpCommand;
+
+/// A command that tracks SAN of a character.
+pub struct SanityCommand;
+
+/// A change of a value evaluated from the options.
+struct Change {
+    /// Whether the value decreases.
+    decrease: bool,
+    /// A name of the option given.
+    option: &'static str,
+    expr: String,
+    amount: i32,
+    /// A description of the dice rolled.
+    dice: String,
+}
+
+impl Change {
+    /// Evaluates the expression given to either of the options.
+    /// Returns `None` if neither is given.
+    async fn from_options(
+        interaction: &CommandInteraction,
+        decrease: &'static str,
+        increase: &'static str,
+    ) -> Result<std::result::Result<Option<Change>, String>> {
+        let (option, expr) = match (
+            interaction.get_string_option(decrease.into()),
+            interaction.get_string_option(increase.into()),
+        ) {
+            (Some(_), Some(_)) => {
+                return Ok(Err(format!(
+                    "Specify either `{}` or `{}`.",
+                    decrease, increase
+                )))
+            }
+            (Some(expr), None) => (decrease, expr),
+            (None, Some(expr)) => (increase, expr),
+            (None, None) => return Ok(Ok(None)),
+        };
+
+        let evaluated = DiceSource::with_guild(interaction.guild_id, |dice| {
+            RollCommand::evaluate_dice_expr(expr, dice)
+        })
+        .await?;
+        let (amount, dice) = match evaluated {
+            Ok(evaluated) => evaluated,
+            Err(message) => return Ok(Err(message)),
+        };
+
+        Ok(Ok(Some(Change {
+            decrease: option == decrease,
+            option,
+            expr: expr.to_string(),
+            amount: amount.max(0),
+            dice,
+        })))
+    }
+
+    /// Describes the calculation of the new value.
+    fn describe(&self, before: i32, after: i32) -> String {
+        let sign = if self.decrease { '-' } else { '+' };
+        format!(
+            "{} {} {} ({}) = {}",
+            before, sign, self.amount, self.dice, after
+        )
+    }
+}
+
+/// Creates an option to take a dice expression.
+fn expr_option(
+    name: &str,
+    description: &str,
+    name_ja: &str,
+    description_ja: &str,
+) -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::String, name, description)
+        .name_localized("ja", name_ja)
+        .description_localized("ja", description_ja)
+        .max_length(MAX_EXPR_LENGTH)
+}
+
+/// Gets a value of the character by either of the names.
+fn lookup(character: &Character, names: &[&str]) -> Option<i32> {
+    names.iter().find_map(|name| {
+        character
+            .get_characteristic(name)
+            .or_else(|| character.get_skill(name))
+    })
+}
+
+/// Gets a value of the character which the tracker needs.
+fn required_value(character: &Character, names: &[&str]) -> std::result::Result<i32, String> {
+    lookup(character, names).ok_or_else(|| {
+        format!(
+            "{} does not have {}. Set it with `/char set key:{}`.",
+            character.name, names[0], names[0]
+        )
+    })
+}
+
+/// Finds the character designated by the option with its vitals.
+async fn load(
+    interaction: &CommandInteraction,
+) -> Result<std::result::Result<(Character, Vitals), String>> {
+    let character = match CharCommand::find_character(interaction).await? {
+        Ok(character) => character,
+        Err(message) => return Ok(Err(message)),
+    };
+    let vitals = Storage::with(|connection| Vitals::load(connection, character.id)).await?;
+    Ok(Ok((character, vitals)))
+}
+
+/// Saves the vitals, records the change to the session and sends the embed.
+async fn finish(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    character: &Character,
+    vitals: &Vitals,
+    change: Option<(&Change, String)>,
+    embed: CreateEmbed,
+) -> Result<CommandStatus> {
+    Storage::with(|connection| vitals.save(connection, character.id)).await?;
+
+    if let Some((change, result)) = change {
+        let record = RollRecord {
+            roller: character.name.clone(),
+            expression: format!("{} {}", change.option, change.expr),
+            dice: change.dice.clone(),
+            result,
+            tier: None,
+        };
+        SessionCommand::record_command(interaction, &[record]).await?;
+    }
+
+    interaction.send_embed(ctx, embed).await?;
+
+    Ok(CommandStatus::Ok)
+}
+
+/// Adds the conditions to the embed.
+fn add_conditions(mut embed: CreateEmbed, conditions: &[Condition]) -> CreateEmbed {
+    for condition in conditions {
+        embed = embed.field(condition.label(), condition.description(), false);
+    }
+    embed
+}
+
+#[naming]
+#[serenity::async_trait]
+impl BotCommand for HpCommand {
+    fn create(&self) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description("Shows or changes HP of your character.")
+            .description_localized("ja", "キャラクターの耐久力を表示または変更します.")
+            .add_option(expr_option(
+                "damage",
+                "Damage to take (ex. `1d6+1`)",
+                "ダメージ",
+                "受けるダメージ (例: `1d6+1`)",
+            ))
+            .add_option(expr_option(
+                "heal",
+                "HP to heal (ex. `1d3`)",
+                "回復",
+                "回復する耐久力 (例: `1d3`)",
+            ))
+            .add_option(CharCommand::character_option(false))
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let (character, mut vitals) = match load(interaction).await? {
+            Ok(loaded) => loaded,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+        let max_hp = match required_value(&character, &["HP", "耐久力"]) {
+            Ok(max_hp) => max_hp,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+        let change = match Change::from_options(interaction, "damage", "heal").await? {
+            Ok(change) => change,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+
+        let before = vitals.hp.unwrap_or(max_hp);
+        let conditions = match &change {
+            Some(change) if change.decrease => vitals.damage(max_hp, change.amount),
+            Some(change) => {
+                vitals.heal(max_hp, change.amount);
+                Vec::new()
+            }
+            None => vitals.hp_conditions(max_hp),
+        };
+        let after = vitals.hp.unwrap_or(max_hp);
+
+        let embed = CreateEmbed::new()
+            .title(format!("HP of {}", character.name))
+            .field(
+                format!(":heart: HP {} / {}", after, max_hp),
+                change
+                    .as_ref()
+                    .map(|change| change.describe(before, after))
+                    .unwrap_or_else(|| "No change.".to_string()),
+                false,
+            );
+        let embed = add_conditions(embed, &conditions);
+
+        let change = change
+            .as_ref()
+            .map(|change| (change, format!("HP {} -> {}", before, after)));
+        finish(ctx, interaction, &character, &vitals, change, embed).await
+    }
+}
+
+#[naming]
+#[serenity::async_trait]
+impl BotCommand for MpCommand {
+    fn create(&self) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description("Shows or changes MP of your character.")
+            .description_localized(
+                "ja",
+                "キャラクターのマジック・ポイントを表示または変更します.",
+            )
+            .add_option(expr_option(
+                "spend",
+                "MP to spend (ex. `1d6`)",
+                "消費",
+                "消費するマジック・ポイント (例: `1d6`)",
+            ))
+            .add_option(expr_option(
+                "recover",
+                "MP to recover (ex. `3`)",
+                "回復",
+                "回復するマジック・ポイント (例: `3`)",
+            ))
+            .add_option(CharCommand::character_option(false))
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let (character, mut vitals) = match load(interaction).await? {
+            Ok(loaded) => loaded,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+        let max_mp = match required_value(&character, &["MP", "マジック・ポイント"]) {
+            Ok(max_mp) => max_mp,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+        let change = match Change::from_options(interaction, "spend", "recover").await? {
+            Ok(change) => change,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+
+        let before = vitals.mp.unwrap_or(max_mp);
+        match &change {
+            Some(change) if change.decrease => vitals.spend_mp(max_mp, change.amount),
+            Some(change) => vitals.recover_mp(max_mp, change.amount),
+            None => (),
+        }
+        let after = vitals.mp.unwrap_or(max_mp);
+
+        let embed = CreateEmbed::new()
+            .title(format!("MP of {}", character.name))
+            .field(
+                format!(":magic_wand: MP {} / {}", after, max_mp),
+                change
+                    .as_ref()
+                    .map(|change| change.describe(before, after))
+                    .unwrap_or_else(|| "No change.".to_string()),
+                false,
+            );
+
+        let change = change
+            .as_ref()
+            .map(|change| (change, format!("MP {} -> {}", before, after)));
+        finish(ctx, interaction, &character, &vitals, change, embed).await
+    }
+}
+
+#[naming]
+#[serenity::async_trait]
+impl BotCommand for SanityCommand {
+    fn create(&self) -> CreateCommand {
+        CreateCommand::new(self.name())
+            .description("Shows or changes SAN of your character.")
+            .description_localized("ja", "キャラクターの正気度を表示または変更します.")
+            .add_option(expr_option(
+                "lose",
+                "SAN to lose (ex. `1d6`)",
+                "減少",
+                "減少する正気度 (例: `1d6`)",
+            ))
+            .add_option(expr_option(
+                "gain",
+                "SAN to gain (ex. `1d10`)",
+                "増加",
+                "増加する正気度 (例: `1d10`)",
+            ))
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Boolean,
+                    "new_day",
+                    "Whether a new game day starts before the change",
+                )
+                .name_localized("ja", "新しい日")
+                .description_localized("ja", "変更の前に新しい日を始めるかどうか"),
+            )
+            .add_option(CharCommand::character_option(false))
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<CommandStatus> {
+        let (character, mut vitals) = match load(interaction).await? {
+            Ok(loaded) => loaded,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+        let initial_san = match required_value(&character, &["SAN", "正気度"]) {
+            Ok(initial_san) => initial_san,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+        let change = match Change::from_options(interaction, "lose", "gain").await? {
+            Ok(change) => change,
+            Err(message) => return Ok(CommandStatus::Err(message)),
+        };
+
+        let mythos = lookup(&character, &["Cthulhu Mythos", "クトゥルフ神話"]).unwrap_or(0);
+        let max_san = MAX_SAN - mythos;
+
+        if interaction.get_bool_option("new_day".into()) == Some(true) {
+            vitals.new_day(initial_san);
+        }
+
+        let before = vitals.san.unwrap_or(initial_san);
+        let conditions = match &change {
+            Some(change) if change.decrease => vitals.lose_san(initial_san, change.amount),
+            Some(change) => {
+                vitals.gain_san(initial_san, max_san, change.amount);
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        let after = vitals.san.unwrap_or(initial_san);
+
+        let day_start = vitals.san_day_start.unwrap_or(after);
+        let embed = CreateEmbed::new()
+            .title(format!("SAN of {}", character.name))
+            .field(
+                format!(":brain: SAN {} / {}", after, max_san),
+                change
+                    .as_ref()
+                    .map(|change| change.describe(before, after))
+                    .unwrap_or_else(|| "No change.".to_string()),
+                false,
+            )
+            .field(
+                "Lost in this game day",
+                format!(
+                    "{} (indefinite insanity at {})",
+                    vitals.san_lost_today,
+                    day_start / 5
+                ),
+                false,
+            );
+        let embed = add_conditions(embed, &conditions);
+
+        let change = change
+            .as_ref()
+            .map(|change| (change, format!("SAN {} -> {}", before, after)));
+        finish(ctx, interaction, &character, &vitals, change, embed).await
+    }
+}
@@ -39,6 +39,8 @@ pub struct BotConfig {
     pub log_path: String,
     pub status_message: String,
     pub database_url: Option<String>,
+    /// A path to the JSON of the madness tables. The built-in ones are used if it is omitted.
+    pub madness_tables: Option<String>,
 }
 
 /// Holds the configurations of this bot. You need to call `BotConfig::load_from_file` before using this.
@@ -0,0 +1,190 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use rusqlite::Connection;
+use serenity::model::id::GuildId;
+use tyche::dice::roller::Roller;
+use tyche::dice::DieRoll;
+
+use crate::storage::seed::GuildSeed;
+use crate::storage::Storage;
+
+/// A source of random numbers which all of the dice rolls draw from.
+pub struct DiceSource {
+    rng: StdRng,
+    /// The number of the 32-bit words drawn so far.
+    /// `StdRng` produces a stream of the words, so skipping them restores the state.
+    position: u64,
+}
+
+/// Sources of the guilds which have a seed, cached not to skip the words on every roll.
+/// A guild shares one source so that its whole session can be replayed from the seed.
+static GUILD_SOURCES: Lazy<Mutex<HashMap<GuildId, (u64, DiceSource)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+impl DiceSource {
+    /// Creates a cryptographically secure source seeded by the OS.
+    pub fn secure() -> Self {
+        DiceSource {
+            rng: StdRng::from_entropy(),
+            position: 0,
+        }
+    }
+
+    /// Creates a reproducible source from the seed.
+    pub fn seeded(seed: u64) -> Self {
+        DiceSource {
+            rng: StdRng::seed_from_u64(seed),
+            position: 0,
+        }
+    }
+
+    /// Creates a reproducible source from the seed, skipping the words already drawn.
+    pub fn seeded_at(seed: u64, position: u64) -> Self {
+        let mut source = DiceSource::seeded(seed);
+        for _ in 0..position {
+            source.next_u32();
+        }
+        source
+    }
+
+    /// Runs `f` with the source for the guild.
+    /// The seeded source is used if the guild has a seed, otherwise a secure one is used.
+    pub async fn with_guild<T>(
+        guild_id: Option<GuildId>,
+        f: impl FnOnce(&mut DiceSource) -> T,
+    ) -> Result<T> {
+        match guild_id {
+            Some(guild_id) => {
+                Storage::with(|connection| DiceSource::with_guild_in(connection, guild_id, f)).await
+            }
+            None => Ok(f(&mut DiceSource::secure())),
+        }
+    }
+
+    /// Runs `f` with the source for the guild, recording how far its seed has been drawn.
+    fn with_guild_in<T>(
+        connection: &Connection,
+        guild_id: GuildId,
+        f: impl FnOnce(&mut DiceSource) -> T,
+    ) -> Result<T> {
+        let seed = match GuildSeed::get(connection, guild_id)? {
+            Some(seed) => seed,
+            None => return Ok(f(&mut DiceSource::secure())),
+        };
+
+        let mut sources = GUILD_SOURCES.lock().unwrap();
+        let source = match sources.get_mut(&guild_id) {
+            Some((cached, source)) if *cached == seed.seed && source.position == seed.position => {
+                source
+            }
+            _ => {
+                let source = DiceSource::seeded_at(seed.seed, seed.position);
+                sources.insert(guild_id, (seed.seed, source));
+                &mut sources.get_mut(&guild_id).unwrap().1
+            }
+        };
+
+        let result = f(source);
+        GuildSeed::advance(connection, guild_id, source.position)?;
+        Ok(result)
+    }
+}
+
+impl RngCore for DiceSource {
+    fn next_u32(&mut self) -> u32 {
+        self.position += 1;
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.position += 2;
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.position += (dest.len() as u64).div_ceil(4);
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.position += (dest.len() as u64).div_ceil(4);
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+impl Roller for DiceSource {
+    fn roll_die(&mut self, sides: u8) -> DieRoll {
+        if sides > 0 {
+            DieRoll::new(self.gen_range(1..=sides))
+        } else {
+            DieRoll::new(0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tyche::Expr;
+
+    use super::*;
+    use crate::storage::open_in_memory;
+
+    #[test]
+    fn seeded_sources_are_reproducible() {
+        let expr: Expr = "3d6 + 1d100".parse().unwrap();
+
+        let mut first = DiceSource::seeded(1234);
+        let mut second = DiceSource::seeded(1234);
+        for _ in 0..100 {
+            let first = expr.eval(&mut first).unwrap().calc().unwrap();
+            let second = expr.eval(&mut second).unwrap().calc().unwrap();
+            assert_eq!(first, second);
+        }
+    }
+
+    #[test]
+    fn seeded_sources_resume_from_the_position() {
+        // Mix the draws of the different sizes so that they cross the boundaries of the blocks.
+        let mut source = DiceSource::seeded(99);
+        let mut bytes = [0u8; 7];
+        for _ in 0..100 {
+            source.next_u32();
+            source.next_u64();
+            source.fill_bytes(&mut bytes);
+            source.gen_range(1..=100);
+        }
+
+        let mut resumed = DiceSource::seeded_at(99, source.position);
+        for _ in 0..100 {
+            assert_eq!(source.next_u64(), resumed.next_u64());
+        }
+    }
+
+    #[test]
+    fn guild_seed_replays_the_session() {
+        let connection = open_in_memory();
+        // Not shared with the other tests since the sources are cached globally.
+        let guild_id = GuildId::new(7_001);
+
+        let roll = || {
+            DiceSource::with_guild_in(&connection, guild_id, |dice| dice.gen_range(1..=100))
+                .unwrap()
+        };
+
+        GuildSeed::set(&connection, guild_id, 42).unwrap();
+        let session = (0..10).map(|_| roll()).collect::<Vec<i32>>();
+
+        // The rolls continue even if the cached source is lost (ex. on restart).
+        GuildSeed::set(&connection, guild_id, 42).unwrap();
+        let mut replayed = (0..5).map(|_| roll()).collect::<Vec<i32>>();
+        GUILD_SOURCES.lock().unwrap().remove(&guild_id);
+        replayed.extend((0..5).map(|_| roll()));
+
+        assert_eq!(session, replayed);
+    }
+}
@@ -0,0 +1,162 @@
+/// The number of buckets of the d100 histogram. Each one holds 10 faces (ex. 1-10).
+pub const BUCKETS: usize = 10;
+
+/// The minimum expected count of each bucket for the chi-square test to be reliable.
+const MIN_EXPECTED: f64 = 5.0;
+
+/// A result of the chi-square goodness-of-fit test against a fair d100.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChiSquare {
+    pub statistic: f64,
+    pub degrees_of_freedom: u32,
+    /// The probability that a fair d100 gives the statistic or larger.
+    pub p_value: f64,
+}
+
+/// Counts the d100 results in the buckets of 1-10, 11-20, ..., 91-100.
+/// The values out of 1-100 are ignored.
+pub fn histogram(values: &[i32]) -> [u32; BUCKETS] {
+    let mut buckets = [0; BUCKETS];
+    for value in values.iter().filter(|value| (1..=100).contains(*value)) {
+        buckets[((value - 1) / 10) as usize] += 1;
+    }
+    buckets
+}
+
+/// Tests whether the histogram fits the uniform distribution.
+/// Returns `None` if there are too few results to test.
+pub fn chi_square(histogram: &[u32]) -> Option<ChiSquare> {
+    let total = histogram.iter().sum::<u32>() as f64;
+    let expected = total / histogram.len() as f64;
+    if histogram.len() < 2 || expected < MIN_EXPECTED {
+        return None;
+    }
+
+    let statistic = histogram
+        .iter()
+        .map(|observed| (*observed as f64 - expected).powi(2) / expected)
+        .sum::<f64>();
+    let degrees_of_freedom = histogram.len() as u32 - 1;
+
+    Some(ChiSquare {
+        statistic,
+        degrees_of_freedom,
+        p_value: upper_gamma(degrees_of_freedom as f64 / 2.0, statistic / 2.0),
+    })
+}
+
+/// Computes the regularized upper incomplete gamma function Q(a, x).
+fn upper_gamma(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+
+    let ln_prefix = a * x.ln() - x - ln_gamma(a);
+
+    if x < a + 1.0 {
+        // The series of P(a, x) converges quickly here.
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        let mut n = a;
+        for _ in 0..1000 {
+            n += 1.0;
+            term *= x / n;
+            sum += term;
+            if term.abs() < sum.abs() * 1e-15 {
+                break;
+            }
+        }
+        1.0 - sum * ln_prefix.exp()
+    } else {
+        // The continued fraction of Q(a, x) by the modified Lentz's method.
+        let tiny = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..1000 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < tiny {
+                d = tiny;
+            }
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < 1e-15 {
+                break;
+            }
+        }
+        h * ln_prefix.exp()
+    }
+}
+
+/// Computes ln(Γ(x)) for x > 0 by the Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let series = COEFFICIENTS
+        .iter()
+        .enumerate()
+        .fold(1.000000000190015, |series, (i, coefficient)| {
+            series + coefficient / (x + 1.0 + i as f64)
+        });
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-4,
+            "{} is not close to {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn histogram_buckets() {
+        let buckets = histogram(&[1, 10, 11, 50, 91, 100, 0, 101]);
+        assert_eq!(buckets, [2, 1, 0, 0, 1, 0, 0, 0, 0, 2]);
+    }
+
+    #[test]
+    fn chi_square_of_fair_dice() {
+        let result = chi_square(&[10; BUCKETS]).unwrap();
+        assert_close(result.statistic, 0.0);
+        assert_eq!(result.degrees_of_freedom, 9);
+        assert_close(result.p_value, 1.0);
+
+        // Too few results to test.
+        assert!(chi_square(&[4; BUCKETS]).is_none());
+    }
+
+    #[test]
+    fn chi_square_p_values() {
+        // The critical values of the chi-square distribution with 9 degrees of freedom.
+        assert_close(upper_gamma(4.5, 16.919 / 2.0), 0.05);
+        assert_close(upper_gamma(4.5, 21.666 / 2.0), 0.01);
+        assert_close(upper_gamma(4.5, 8.343 / 2.0), 0.5);
+
+        let result = chi_square(&[30, 10, 10, 10, 10, 10, 10, 10, 10, 10]).unwrap();
+        assert_close(result.statistic, 30.0);
+        assert!(result.p_value < 0.001);
+    }
+}
@@ -25,10 +25,23 @@ impl EventHandler for BotHandler {
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        if interaction.kind() == InteractionType::Command {
-            let interaction = interaction.command().unwrap();
-            let result = BotCommandManager::run_command(&ctx, &interaction).await;
-            Logger::log_err(&result).await;
+        match interaction.kind() {
+            InteractionType::Command => {
+                let interaction = interaction.command().unwrap();
+                let result = BotCommandManager::run_command(&ctx, &interaction).await;
+                Logger::log_err(&result).await;
+            }
+            InteractionType::Autocomplete => {
+                let interaction = interaction.autocomplete().unwrap();
+                let result = BotCommandManager::run_autocomplete(&ctx, &interaction).await;
+                Logger::log_err(&result).await;
+            }
+            InteractionType::Component => {
+                let interaction = interaction.message_component().unwrap();
+                let result = BotCommandManager::run_component(&ctx, &interaction).await;
+                Logger::log_err(&result).await;
+            }
+            _ => {}
         }
     }
 }
@@ -23,7 +23,7 @@ static LOG_SENDER: OnceCell<Sender<String>> = OnceCell::new();
 
 impl Logger {
     pub fn init() {
-        if let Err(_) = log::set_logger(&LOGGER) {
+        if log::set_logger(&LOGGER).is_err() {
             panic!("Failed to set the logger.");
         }
         log::set_max_level(log::LevelFilter::Info);
@@ -42,7 +42,7 @@ impl Logger {
         });
 
         // Initialize the log sender.
-        if let Err(_) = LOG_SENDER.set(tx) {
+        if LOG_SENDER.set(tx).is_err() {
             panic!("Re-initialized the log sender.");
         }
 
@@ -192,7 +192,7 @@ impl BotEventCounter {
     pub async fn report_to_log() {
         let mut counter = EVENT_COUNTERS.lock().await;
 
-        let report = if counter.len() == 0 {
+        let report = if counter.is_empty() {
             "  Nothing".to_string()
         } else {
             counter
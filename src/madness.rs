@@ -0,0 +1,186 @@
+use std::fs;
+
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use tyche::dice::roller::Max;
+use tyche::Expr;
+
+use crate::config::{BotConfig, BotConfigError};
+
+/// The tables used when `madness_tables` is not specified.
+const DEFAULT_TABLES: &str = include_str!("../data/madness.json");
+
+/// Holds the tables. You need to call `MadnessTables::init` before using this.
+static TABLES: OnceCell<MadnessTables> = OnceCell::new();
+
+/// A language which the entries are written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Language {
+    En,
+    Ja,
+}
+
+impl Language {
+    /// Gets the language from a locale of Discord (ex. `ja`, `en-US`).
+    /// English is used for the ones not supported.
+    pub fn from_locale(locale: &str) -> Self {
+        if locale.starts_with("ja") {
+            Language::Ja
+        } else {
+            Language::En
+        }
+    }
+}
+
+/// A text written in English and Japanese.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct Localized {
+    pub en: String,
+    pub ja: String,
+}
+
+impl Localized {
+    pub fn get(&self, language: Language) -> &str {
+        match language {
+            Language::En => &self.en,
+            Language::Ja => &self.ja,
+        }
+    }
+}
+
+/// A random table of madness.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct MadnessTable {
+    /// Dice to choose an entry (ex. `1d10`).
+    pub dice: String,
+    /// Dice to decide how long the madness lasts.
+    pub duration: String,
+    /// A unit of the duration (ex. rounds).
+    pub unit: Localized,
+    /// The entries. The n-th one is chosen when the dice show n.
+    pub entries: Vec<Localized>,
+}
+
+impl MadnessTable {
+    /// Gets the entry for the result of the dice.
+    pub fn entry(&self, rolled: i32) -> Option<&Localized> {
+        if rolled < 1 {
+            return None;
+        }
+        self.entries.get(rolled as usize - 1)
+    }
+
+    /// Checks that the dice are valid and every result has its entry.
+    fn validate(&self, name: &str) -> Result<(), String> {
+        let max = |expr: &str| -> Option<i32> {
+            let expr: Expr = expr.parse().ok()?;
+            expr.eval(&mut Max).ok()?.calc().ok()
+        };
+
+        let dice_max = max(&self.dice)
+            .ok_or_else(|| format!("The dice of \"{}\" are invalid: {}", name, self.dice))?;
+        if max(&self.duration).is_none() {
+            return Err(format!(
+                "The duration of \"{}\" is invalid: {}",
+                name, self.duration
+            ));
+        }
+        if dice_max < 1 || dice_max as usize != self.entries.len() {
+            return Err(format!(
+                "\"{}\" has {} entries, but {} can show up to {}.",
+                name,
+                self.entries.len(),
+                self.dice,
+                dice_max
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The tables rolled when an investigator goes insane.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+pub struct MadnessTables {
+    /// Bouts of madness played out in real time.
+    pub realtime: MadnessTable,
+    /// Bouts of madness skipped over by the keeper.
+    pub summary: MadnessTable,
+    pub phobia: MadnessTable,
+    pub mania: MadnessTable,
+}
+
+impl MadnessTables {
+    /// Loads the tables from `madness_tables`, or the default ones if it is not specified.
+    pub fn init() -> Result<()> {
+        let tables = match &BotConfig::get().madness_tables {
+            Some(path) => parse(&fs::read_to_string(path)?),
+            None => parse(DEFAULT_TABLES),
+        }
+        .map_err(|message| BotConfigError::new(&message))?;
+
+        if TABLES.set(tables).is_err() {
+            panic!("Re-initialized the madness tables.");
+        }
+
+        Ok(())
+    }
+
+    /// Gets the tables.
+    pub fn get() -> &'static MadnessTables {
+        TABLES
+            .get()
+            .expect("The madness tables are not initialized.")
+    }
+}
+
+/// Reads the tables from a JSON.
+pub fn parse(json: &str) -> Result<MadnessTables, String> {
+    let tables: MadnessTables = serde_json::from_str(json)
+        .map_err(|err| format!("The madness tables are invalid. ({})", err))?;
+
+    tables.realtime.validate("realtime")?;
+    tables.summary.validate("summary")?;
+    tables.phobia.validate("phobia")?;
+    tables.mania.validate("mania")?;
+
+    Ok(tables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_tables_are_valid() {
+        let tables = parse(DEFAULT_TABLES).unwrap();
+        assert_eq!(tables.realtime.entries.len(), 10);
+        assert_eq!(tables.summary.entries.len(), 10);
+
+        let phobia = tables.phobia.entry(100).unwrap();
+        assert!(phobia.get(Language::En).starts_with("Kenophobia"));
+        assert!(phobia.get(Language::Ja).starts_with("空虚恐怖症"));
+        assert!(tables.mania.entry(0).is_none());
+        assert!(tables.mania.entry(101).is_none());
+    }
+
+    #[test]
+    fn entries_must_match_the_dice() {
+        let mut tables = parse(DEFAULT_TABLES).unwrap();
+        tables.realtime.dice = "1d8".to_string();
+        assert!(tables.realtime.validate("realtime").is_err());
+
+        tables.realtime.dice = "1d10".to_string();
+        tables.realtime.duration = "a few".to_string();
+        assert!(tables.realtime.validate("realtime").is_err());
+
+        assert!(parse("{}").is_err());
+    }
+
+    #[test]
+    fn language_from_locale() {
+        assert_eq!(Language::from_locale("ja"), Language::Ja);
+        assert_eq!(Language::from_locale("en-US"), Language::En);
+        assert_eq!(Language::from_locale("fr"), Language::En);
+    }
+}
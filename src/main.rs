@@ -9,6 +9,8 @@ use serenity::Client;
 use crate::config::BotConfig;
 use crate::handler::BotHandler;
 use crate::logging::Logger;
+use crate::madness::MadnessTables;
+use crate::storage::Storage;
 
 /// Initializes a bot and lets the bot start.
 async fn start_bot() -> Result<()> {
@@ -38,6 +40,12 @@ async fn start_process() -> Result<()> {
 
     Logger::enable_daily_reports();
 
+    // Open the database and apply the migrations.
+    Storage::init()?;
+
+    // Load the tables of madness.
+    MadnessTables::init()?;
+
     start_bot().await?;
 
     Ok(())
@@ -51,7 +59,17 @@ async fn main() {
     Logger::log_err(&result).await;
 }
 
+pub mod ccfolia;
+pub mod combat;
 pub mod commands;
 pub mod config;
+pub mod dice;
+pub mod fairness;
 pub mod handler;
 pub mod logging;
+pub mod madness;
+pub mod random_table;
+pub mod rules;
+pub mod skills;
+pub mod storage;
+pub mod vitals;
@@ -0,0 +1,727 @@
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tyche::dice::roller::{Max, Val};
+use tyche::Expr;
+
+use crate::commands::roll::RollRequest;
+
+/// The maximum number of rows of a table.
+pub const MAX_ROWS: usize = 1000;
+
+/// The maximum length of a text of a row.
+pub const MAX_TEXT_LENGTH: usize = 1000;
+
+/// The maximum depth of the tables referring to other tables.
+pub const MAX_DEPTH: usize = 5;
+
+/// The maximum number of the tables and the expressions resolved in a roll.
+pub const MAX_EXPANSIONS: usize = 100;
+
+/// The maximum number of dice rolled in a roll, including the ones to choose the rows.
+pub const MAX_DICE: usize = 100;
+
+/// The maximum length of a text resolved in a roll.
+pub const MAX_RESOLVED_LENGTH: usize = 4000;
+
+/// Matches a row with a dice range such as `01-15: Cultist` or `16: Ghoul`.
+static RANGE_ROW: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\d+)\s*(?:[-–~]\s*(\d+))?\s*:\s*(.*)$").unwrap());
+
+/// Matches a row with a weight such as `3x: Cultist`.
+static WEIGHT_ROW: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\d+)\s*[x×]\s*:\s*(.*)$").unwrap());
+
+/// Matches a line to designate the dice such as `dice: 2d6`.
+static DICE_LINE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)^dice\s*:\s*(.+)$").unwrap());
+
+/// Matches a dice expression `{1d6}` or a reference to another table `[[weather]]` in a row.
+static TOKEN: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{([^{}]+)\}|\[\[([^\[\]]+)\]\]").unwrap());
+
+/// A format of the file which a table is written in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableFormat {
+    Json,
+    Csv,
+    Text,
+}
+
+impl TableFormat {
+    /// Guesses the format from the extension of the file.
+    pub fn from_file_name(name: &str) -> Self {
+        let name = name.to_lowercase();
+        if name.ends_with(".json") {
+            TableFormat::Json
+        } else if name.ends_with(".csv") {
+            TableFormat::Csv
+        } else {
+            TableFormat::Text
+        }
+    }
+}
+
+/// A row of a table chosen when the dice show a number from `from` to `to`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableRow {
+    pub from: i32,
+    pub to: i32,
+    /// A text which may contain dice expressions (`{1d6}`) and references to other tables (`[[name]]`).
+    pub text: String,
+}
+
+/// A random table defined by a user.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RandomTable {
+    /// Dice to choose a row (ex. `1d100`).
+    pub dice: String,
+    /// The rows sorted by their ranges.
+    pub rows: Vec<TableRow>,
+}
+
+/// A result of rolling a table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableRoll {
+    pub rolled: i32,
+    /// A description of the dice rolled.
+    pub dice: String,
+    /// The text of the row with its expressions and references resolved.
+    pub text: String,
+    /// The dice expressions and the tables rolled in the row.
+    pub details: Vec<String>,
+}
+
+/// Counts how much a roll has resolved not to let a table expand without limit.
+#[derive(Default)]
+struct RollBudget {
+    expansions: usize,
+    dice: usize,
+}
+
+impl RollBudget {
+    /// Spends the budget to resolve a table or a dice expression.
+    fn expand(&mut self, expr: &str) -> Result<(), String> {
+        self.expansions += 1;
+        if self.expansions > MAX_EXPANSIONS {
+            return Err(format!(
+                "Up to {} tables and expressions can be resolved in a roll.",
+                MAX_EXPANSIONS
+            ));
+        }
+
+        // An invalid expression is reported when it is evaluated.
+        if let Ok(expr) = expr.parse::<Expr>() {
+            self.dice += RollRequest::count_dice(&expr);
+        }
+        if self.dice > MAX_DICE {
+            return Err(format!("Up to {} dice can be rolled in a roll.", MAX_DICE));
+        }
+
+        Ok(())
+    }
+}
+
+/// Represents how a row is chosen before the ranges are calculated.
+enum Chance {
+    Range(i32, i32),
+    Weight(i32),
+}
+
+impl RandomTable {
+    /// Gets the row for the result of the dice.
+    pub fn row(&self, rolled: i32) -> Option<&TableRow> {
+        self.rows
+            .iter()
+            .find(|row| row.from <= rolled && rolled <= row.to)
+    }
+
+    /// Rolls the table. `tables` are the ones which can be referred to by their lowercased names.
+    /// `evaluate` evaluates a dice expression and describes it.
+    pub fn roll(
+        &self,
+        tables: &HashMap<String, RandomTable>,
+        evaluate: &mut impl FnMut(&str) -> Result<(i32, String), String>,
+    ) -> Result<TableRoll, String> {
+        let mut details = Vec::new();
+        let mut budget = RollBudget::default();
+        let (rolled, dice, text) =
+            self.roll_nested(tables, evaluate, &mut details, &mut budget, 0)?;
+        Ok(TableRoll {
+            rolled,
+            dice,
+            text,
+            details,
+        })
+    }
+
+    fn roll_nested(
+        &self,
+        tables: &HashMap<String, RandomTable>,
+        evaluate: &mut impl FnMut(&str) -> Result<(i32, String), String>,
+        details: &mut Vec<String>,
+        budget: &mut RollBudget,
+        depth: usize,
+    ) -> Result<(i32, String, String), String> {
+        if depth > MAX_DEPTH {
+            return Err(format!(
+                "The tables refer to each other more than {} times.",
+                MAX_DEPTH
+            ));
+        }
+
+        budget.expand(&self.dice)?;
+        let (rolled, dice) = evaluate(&self.dice)?;
+        let row = self
+            .row(rolled)
+            .ok_or_else(|| format!("The table has no row for {}.", rolled))?;
+
+        let mut text = String::new();
+        let mut last = 0;
+        for captures in TOKEN.captures_iter(&row.text) {
+            let token = captures.get(0).unwrap();
+            text.push_str(&row.text[last..token.start()]);
+            last = token.end();
+
+            if let Some(expr) = captures.get(1) {
+                budget.expand(expr.as_str())?;
+                let (evaluated, description) = evaluate(expr.as_str())?;
+                details.push(format!("`{}` {}", expr.as_str(), description));
+                text.push_str(&evaluated.to_string());
+            } else {
+                let name = captures.get(2).unwrap().as_str().trim();
+                let table = tables
+                    .get(&name.to_lowercase())
+                    .ok_or_else(|| format!("No table is named \"{}\".", name))?;
+                let (rolled, dice, nested) =
+                    table.roll_nested(tables, evaluate, details, budget, depth + 1)?;
+                details.push(format!("[[{}]] {} ({})", name, rolled, dice));
+                text.push_str(&nested);
+            }
+
+            if text.len() > MAX_RESOLVED_LENGTH {
+                return Err(format!(
+                    "The result must be {} bytes or fewer.",
+                    MAX_RESOLVED_LENGTH
+                ));
+            }
+        }
+        text.push_str(&row.text[last..]);
+
+        Ok((rolled, dice, text))
+    }
+
+    /// Builds a table from the rows. Dice ranges and weights cannot be mixed.
+    fn build(dice: Option<String>, rows: Vec<(Option<Chance>, String)>) -> Result<Self, String> {
+        if rows.is_empty() {
+            return Err("The table has no rows.".to_string());
+        }
+        if rows.len() > MAX_ROWS {
+            return Err(format!("A table can have up to {} rows.", MAX_ROWS));
+        }
+        for (_, text) in rows.iter() {
+            if text.is_empty() {
+                return Err("Every row must have a text.".to_string());
+            }
+            if text.chars().count() > MAX_TEXT_LENGTH {
+                return Err(format!(
+                    "A row must be {} characters or fewer.",
+                    MAX_TEXT_LENGTH
+                ));
+            }
+        }
+
+        let ranged = rows
+            .iter()
+            .filter(|(chance, _)| matches!(chance, Some(Chance::Range(_, _))))
+            .count();
+        if ranged == 0 {
+            RandomTable::build_weighted(dice, rows)
+        } else if ranged == rows.len() {
+            RandomTable::build_ranged(dice, rows)
+        } else {
+            Err("Give dice ranges to all of the rows or none of them.".to_string())
+        }
+    }
+
+    /// Builds a table whose rows are chosen by their weights.
+    fn build_weighted(
+        dice: Option<String>,
+        rows: Vec<(Option<Chance>, String)>,
+    ) -> Result<Self, String> {
+        if dice.is_some() {
+            return Err("Dice cannot be designated for a table with weights.".to_string());
+        }
+
+        let mut table_rows = Vec::new();
+        let mut total: i32 = 0;
+        for (chance, text) in rows {
+            let weight = match chance {
+                Some(Chance::Weight(weight)) => weight,
+                _ => 1,
+            };
+            if weight < 1 {
+                return Err(format!("The weight of \"{}\" must be positive.", text));
+            }
+            let to = match total.checked_add(weight) {
+                Some(to) => to,
+                None => return Err("The total of the weights is too large.".to_string()),
+            };
+            table_rows.push(TableRow {
+                from: total + 1,
+                to,
+                text,
+            });
+            total = to;
+        }
+
+        Ok(RandomTable {
+            dice: format!("1d{}", total),
+            rows: table_rows,
+        })
+    }
+
+    /// Builds a table whose rows are chosen by dice ranges.
+    fn build_ranged(
+        dice: Option<String>,
+        rows: Vec<(Option<Chance>, String)>,
+    ) -> Result<Self, String> {
+        let mut rows = rows
+            .into_iter()
+            .filter_map(|(chance, text)| match chance {
+                Some(Chance::Range(from, to)) => Some(TableRow { from, to, text }),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        rows.sort_by_key(|row| row.from);
+
+        for row in rows.iter() {
+            if row.from > row.to {
+                return Err(format!("The range {}-{} is reversed.", row.from, row.to));
+            }
+        }
+        for pair in rows.windows(2) {
+            if pair[0].to >= pair[1].from {
+                return Err(format!(
+                    "The ranges {}-{} and {}-{} overlap.",
+                    pair[0].from, pair[0].to, pair[1].from, pair[1].to
+                ));
+            }
+            match pair[0].to.checked_add(1) {
+                Some(next) if next < pair[1].from => {
+                    return Err(format!("No row covers {}.", next));
+                }
+                _ => {}
+            }
+        }
+
+        let min = rows.first().unwrap().from;
+        let max = rows.last().unwrap().to;
+        let dice = match dice {
+            Some(dice) => {
+                let bounds = |roll_max: bool| -> Option<i32> {
+                    let expr: Expr = dice.parse().ok()?;
+                    let evaluated = if roll_max {
+                        expr.eval(&mut Max)
+                    } else {
+                        expr.eval(&mut Val(1))
+                    };
+                    evaluated.ok()?.calc().ok()
+                };
+                match (bounds(false), bounds(true)) {
+                    (Some(low), Some(high)) if low == min && high == max => dice,
+                    (Some(low), Some(high)) => {
+                        return Err(format!(
+                            "{} shows {}-{}, but the rows cover {}-{}.",
+                            dice, low, high, min, max
+                        ))
+                    }
+                    _ => return Err(format!("The dice are invalid: {}", dice)),
+                }
+            }
+            None if min == 1 => format!("1d{}", max),
+            None => {
+                return Err(format!(
+                    "The rows start from {}. Designate the dice (ex. `dice: 2d6`).",
+                    min
+                ))
+            }
+        };
+
+        Ok(RandomTable { dice, rows })
+    }
+}
+
+/// Reads a table from a file.
+pub fn parse(source: &str, format: TableFormat) -> Result<RandomTable, String> {
+    match format {
+        TableFormat::Json => parse_json(source),
+        TableFormat::Csv => parse_csv(source),
+        TableFormat::Text => parse_text(source),
+    }
+}
+
+/// Reads the number at the end of a range. `00` stands for 100 as on the percentile dice.
+fn range_end(number: &str) -> Result<i32, String> {
+    if number == "00" {
+        return Ok(100);
+    }
+    number
+        .parse()
+        .map_err(|_| format!("{} is too large for a range.", number))
+}
+
+/// Reads a row such as `01-15: Cultist`, `3x: Cultist` or `Cultist`.
+fn parse_row(line: &str) -> Result<(Option<Chance>, String), String> {
+    if let Some(captures) = WEIGHT_ROW.captures(line) {
+        let weight = range_end(&captures[1])?;
+        return Ok((Some(Chance::Weight(weight)), captures[2].trim().to_string()));
+    }
+    if let Some(captures) = RANGE_ROW.captures(line) {
+        let from = range_end(&captures[1])?;
+        let to = match captures.get(2) {
+            Some(to) => range_end(to.as_str())?,
+            None => from,
+        };
+        return Ok((
+            Some(Chance::Range(from, to)),
+            captures[3].trim().to_string(),
+        ));
+    }
+    Ok((None, line.trim().to_string()))
+}
+
+/// Reads a table written in lines. Empty lines and the ones starting with `#` are skipped.
+/// The first line can designate the dice such as `dice: 2d6`.
+fn parse_text(source: &str) -> Result<RandomTable, String> {
+    let mut lines = source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .peekable();
+
+    let dice = lines
+        .peek()
+        .and_then(|line| DICE_LINE.captures(line))
+        .map(|captures| captures[1].trim().to_string());
+    if dice.is_some() {
+        lines.next();
+    }
+
+    let rows = lines.map(parse_row).collect::<Result<Vec<_>, _>>()?;
+    RandomTable::build(dice, rows)
+}
+
+/// Splits a CSV into records. Fields can be quoted with `"`.
+fn csv_records(source: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => (),
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("A quote of the CSV is not closed.".to_string());
+    }
+    record.push(field);
+    records.push(record);
+
+    Ok(records
+        .into_iter()
+        .filter(|record| record.iter().any(|field| !field.trim().is_empty()))
+        .collect())
+}
+
+/// Reads a table written in CSV. Each record is `text` or `range or weight,text` (ex. `01-15,Cultist`, `3x,Cultist`).
+/// A header such as `weight,text` makes the first column read as the weights.
+fn parse_csv(source: &str) -> Result<RandomTable, String> {
+    let mut records = csv_records(source)?;
+
+    let mut weighted = false;
+    if let Some(header) = records.first() {
+        let first = header[0].trim().to_lowercase();
+        if ["roll", "range", "dice", "weight", "text"].contains(&first.as_str()) {
+            weighted = first == "weight";
+            records.remove(0);
+        }
+    }
+
+    let rows = records
+        .into_iter()
+        .map(|record| match record.as_slice() {
+            [text] => Ok((None, text.trim().to_string())),
+            [chance, text] if weighted => {
+                let weight = range_end(chance.trim())
+                    .map_err(|_| format!("The weight must be a number: {}", chance))?;
+                Ok((Some(Chance::Weight(weight)), text.trim().to_string()))
+            }
+            [chance, text] => match parse_row(&format!("{}: {}", chance.trim(), text)) {
+                Ok((Some(chance), text)) => Ok((Some(chance), text)),
+                _ => Err(format!("A range or a weight is expected: {}", chance)),
+            },
+            _ => Err("Each record of the CSV must have 1 or 2 columns.".to_string()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    RandomTable::build(None, rows)
+}
+
+/// A row of a table written in JSON.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonRow {
+    Text(String),
+    Row {
+        text: String,
+        #[serde(default)]
+        range: Option<JsonRange>,
+        #[serde(default)]
+        weight: Option<i32>,
+    },
+}
+
+/// A range of a row written in JSON (ex. `"01-15"`, `[1, 15]`, `16`).
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonRange {
+    Text(String),
+    Pair([i32; 2]),
+    Single(i32),
+}
+
+/// A table written in JSON. The rows can be given without the dice.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonTable {
+    Rows(Vec<JsonRow>),
+    Table {
+        #[serde(default)]
+        dice: Option<String>,
+        rows: Vec<JsonRow>,
+    },
+}
+
+/// Reads a table written in JSON.
+fn parse_json(source: &str) -> Result<RandomTable, String> {
+    let table: JsonTable =
+        serde_json::from_str(source).map_err(|err| format!("The JSON is invalid. ({})", err))?;
+    let (dice, rows) = match table {
+        JsonTable::Rows(rows) => (None, rows),
+        JsonTable::Table { dice, rows } => (dice, rows),
+    };
+
+    let rows = rows
+        .into_iter()
+        .map(|row| match row {
+            JsonRow::Text(text) => Ok((None, text)),
+            JsonRow::Row {
+                text,
+                range: Some(_),
+                weight: Some(_),
+            } => Err(format!(
+                "\"{}\" cannot have both of a range and a weight.",
+                text
+            )),
+            JsonRow::Row {
+                text,
+                range: Some(range),
+                ..
+            } => {
+                let (from, to) = match range {
+                    JsonRange::Text(range) => match parse_row(&format!("{}: {}", range, text))? {
+                        (Some(Chance::Range(from, to)), _) => (from, to),
+                        _ => return Err(format!("The range is invalid: {}", range)),
+                    },
+                    JsonRange::Pair([from, to]) => (from, to),
+                    JsonRange::Single(value) => (value, value),
+                };
+                Ok((Some(Chance::Range(from, to)), text))
+            }
+            JsonRow::Row {
+                text,
+                weight: Some(weight),
+                ..
+            } => Ok((Some(Chance::Weight(weight)), text)),
+            JsonRow::Row { text, .. } => Ok((None, text)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    RandomTable::build(dice, rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::roll::RollCommand;
+
+    fn ranges(table: &RandomTable) -> Vec<(i32, i32, &str)> {
+        table
+            .rows
+            .iter()
+            .map(|row| (row.from, row.to, row.text.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn text_with_ranges() {
+        let table = parse(
+            "# Encounters\n01-15: Cultist\n16-95: Nothing\n\n96-00: Ghoul\n",
+            TableFormat::Text,
+        )
+        .unwrap();
+        assert_eq!(table.dice, "1d100");
+        assert_eq!(
+            ranges(&table),
+            vec![(1, 15, "Cultist"), (16, 95, "Nothing"), (96, 100, "Ghoul")]
+        );
+        assert_eq!(table.row(15).unwrap().text, "Cultist");
+        assert!(table.row(0).is_none());
+
+        let table = parse(
+            "dice: 2d6\n2-6: Rain\n7: Fog\n8-12: Clear",
+            TableFormat::Text,
+        )
+        .unwrap();
+        assert_eq!(table.dice, "2d6");
+
+        // The dice must show every row.
+        assert!(parse("dice: 1d6\n2-6: Rain\n7: Fog", TableFormat::Text).is_err());
+        assert!(parse("2-6: Rain\n7: Fog", TableFormat::Text).is_err());
+        assert!(parse("1-5: Rain\n5-6: Fog", TableFormat::Text).is_err());
+        assert!(parse("1-3: Rain\n5-6: Fog", TableFormat::Text).is_err());
+        assert!(parse("1-3: Rain\nFog", TableFormat::Text).is_err());
+        assert!(parse("# Nothing", TableFormat::Text).is_err());
+
+        // A range can end at the largest number.
+        let table = parse("1-5: Rain\n6-2147483647: Fog", TableFormat::Text).unwrap();
+        assert_eq!(table.dice, "1d2147483647");
+        assert!(parse("1-2147483647: Rain\n2147483647: Fog", TableFormat::Text).is_err());
+    }
+
+    #[test]
+    fn text_with_weights() {
+        let table = parse("3x: Cultist\nGhoul\n2×: Deep One", TableFormat::Text).unwrap();
+        assert_eq!(table.dice, "1d6");
+        assert_eq!(
+            ranges(&table),
+            vec![(1, 3, "Cultist"), (4, 4, "Ghoul"), (5, 6, "Deep One")]
+        );
+        assert!(parse("0x: Cultist", TableFormat::Text).is_err());
+
+        // The total must not overflow.
+        assert!(parse("2147483647x: Cultist\nGhoul", TableFormat::Text).is_err());
+        assert!(parse("2147483646x: Cultist\nGhoul", TableFormat::Text).is_ok());
+    }
+
+    #[test]
+    fn csv_and_json() {
+        let csv = "roll,text\n01-50,\"Cultist, armed\"\n51-100,\"A \"\"friendly\"\" stranger\"\n";
+        let table = parse(csv, TableFormat::Csv).unwrap();
+        assert_eq!(
+            ranges(&table),
+            vec![
+                (1, 50, "Cultist, armed"),
+                (51, 100, "A \"friendly\" stranger")
+            ]
+        );
+
+        let table = parse("weight,text\n2,Cultist\n1,Ghoul", TableFormat::Csv).unwrap();
+        assert_eq!(ranges(&table), vec![(1, 2, "Cultist"), (3, 3, "Ghoul")]);
+        assert!(parse("a,b,c", TableFormat::Csv).is_err());
+
+        let json = r#"{ "dice": "1d4+1", "rows": [
+            { "range": "2-3", "text": "Rain" },
+            { "range": [4, 4], "text": "Fog" },
+            { "range": 5, "text": "Clear" }
+        ] }"#;
+        let table = parse(json, TableFormat::Json).unwrap();
+        assert_eq!(
+            ranges(&table),
+            vec![(2, 3, "Rain"), (4, 4, "Fog"), (5, 5, "Clear")]
+        );
+
+        let table = parse(
+            r#"["Rain", { "weight": 2, "text": "Fog" }]"#,
+            TableFormat::Json,
+        )
+        .unwrap();
+        assert_eq!(ranges(&table), vec![(1, 1, "Rain"), (2, 3, "Fog")]);
+        assert!(parse(
+            r#"[{ "weight": 2, "range": 1, "text": "Fog" }]"#,
+            TableFormat::Json
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn roll_nested_tables() {
+        let weather = parse("Rain\nFog", TableFormat::Text).unwrap();
+        let encounter = parse("{1d3+1} cultists in [[Weather]]", TableFormat::Text).unwrap();
+        let mut tables = HashMap::new();
+        tables.insert("weather".to_string(), weather);
+        tables.insert("encounters".to_string(), encounter.clone());
+
+        let mut evaluate = |expr: &str| RollCommand::evaluate_dice_expr(expr, &mut Max);
+        let rolled = encounter.roll(&tables, &mut evaluate).unwrap();
+        assert_eq!(rolled.rolled, 1);
+        assert_eq!(rolled.text, "4 cultists in Fog");
+        assert_eq!(rolled.details.len(), 2);
+
+        // A table referring to itself never ends.
+        let looping = parse("[[loop]]", TableFormat::Text).unwrap();
+        tables.insert("loop".to_string(), looping.clone());
+        assert!(looping.roll(&tables, &mut evaluate).is_err());
+
+        let missing = parse("[[nowhere]]", TableFormat::Text).unwrap();
+        assert!(missing.roll(&tables, &mut evaluate).is_err());
+    }
+
+    #[test]
+    fn roll_within_the_budget() {
+        let mut evaluate = |expr: &str| RollCommand::evaluate_dice_expr(expr, &mut Max);
+
+        // Each table refers to the next one many times without looping.
+        let mut tables = HashMap::new();
+        for depth in 0..4 {
+            let text = format!("[[t{}]] ", depth + 1).repeat(10);
+            tables.insert(
+                format!("t{}", depth),
+                parse(&text, TableFormat::Text).unwrap(),
+            );
+        }
+        tables.insert("t4".to_string(), parse("Fog", TableFormat::Text).unwrap());
+        assert!(tables["t0"].roll(&tables, &mut evaluate).is_err());
+
+        // A few references are fine.
+        assert_eq!(
+            tables["t3"]
+                .roll(&tables, &mut evaluate)
+                .unwrap()
+                .text
+                .trim()
+                .len(),
+            39
+        );
+
+        // The dice in the rows count as well as the ones of `/roll`.
+        let many_dice = parse("{1000000000d6}", TableFormat::Text).unwrap();
+        assert!(many_dice.roll(&tables, &mut evaluate).is_err());
+        let dice = parse("{50d6} and {50d6}", TableFormat::Text).unwrap();
+        assert!(dice.roll(&tables, &mut evaluate).is_err());
+    }
+}
@@ -0,0 +1,599 @@
+use std::cmp::Ordering;
+
+use rand::{Rng, RngCore};
+
+/// A tier of the result of a skill roll.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkillTier {
+    /// The best critical such as rolling 1.
+    GreatCritical,
+    Critical,
+    Extreme,
+    Hard,
+    Special,
+    Success,
+    Failure,
+    Fumble,
+    /// The worst fumble such as rolling 100.
+    GreatFumble,
+}
+
+/// All of the tiers from the best to the worst.
+const ALL_TIERS: [SkillTier; 9] = [
+    SkillTier::GreatCritical,
+    SkillTier::Critical,
+    SkillTier::Extreme,
+    SkillTier::Hard,
+    SkillTier::Special,
+    SkillTier::Success,
+    SkillTier::Failure,
+    SkillTier::Fumble,
+    SkillTier::GreatFumble,
+];
+
+impl SkillTier {
+    /// Checks whether the tier means the roll succeeded.
+    pub fn is_success(&self) -> bool {
+        !matches!(
+            self,
+            SkillTier::Failure | SkillTier::Fumble | SkillTier::GreatFumble
+        )
+    }
+
+    /// Gets a label of the tier shown in the embeds.
+    pub fn label(&self) -> &'static str {
+        match self {
+            SkillTier::GreatCritical => ":star::crown::star: **Critical!!!**",
+            SkillTier::Critical => ":crown: **Critical!**",
+            SkillTier::Extreme => ":crown: **Extreme Success!**",
+            SkillTier::Hard => ":o: **Hard Success!**",
+            SkillTier::Special => ":crown: **Special!**",
+            SkillTier::Success => ":o: **Success**",
+            SkillTier::Failure => ":x: **Failed**",
+            SkillTier::Fumble => ":skull: **Fumble!**",
+            SkillTier::GreatFumble => ":fire::skull::fire: **Fumble!!!**",
+        }
+    }
+
+    /// Gets a short name of the tier.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SkillTier::GreatCritical | SkillTier::Critical => "Critical",
+            SkillTier::Extreme => "Extreme",
+            SkillTier::Hard => "Hard",
+            SkillTier::Special => "Special",
+            SkillTier::Success => "Regular",
+            SkillTier::Failure => "Failure",
+            SkillTier::Fumble | SkillTier::GreatFumble => "Fumble",
+        }
+    }
+
+    /// Gets an identifier of the tier. It is used to save the tier as a text.
+    pub fn id(&self) -> &'static str {
+        match self {
+            SkillTier::GreatCritical => "great_critical",
+            SkillTier::Critical => "critical",
+            SkillTier::Extreme => "extreme",
+            SkillTier::Hard => "hard",
+            SkillTier::Special => "special",
+            SkillTier::Success => "success",
+            SkillTier::Failure => "failure",
+            SkillTier::Fumble => "fumble",
+            SkillTier::GreatFumble => "great_fumble",
+        }
+    }
+
+    /// Finds the tier from its identifier.
+    pub fn from_id(id: &str) -> Option<Self> {
+        ALL_TIERS.iter().find(|tier| tier.id() == id).copied()
+    }
+
+    /// Gets a rank of the tier. The better tier has the higher rank.
+    pub fn rank(&self) -> usize {
+        ALL_TIERS.len() - ALL_TIERS.iter().position(|tier| tier == self).unwrap()
+    }
+}
+
+/// A structured result of a skill roll.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SkillOutcome {
+    pub tier: SkillTier,
+    pub rolled: i32,
+    /// How the rolled value is selected (ex. `min([12, 42]) = 12`).
+    pub rolled_text: String,
+    /// How the tier is judged (ex. `<= 60 / 5`).
+    pub threshold: String,
+}
+
+impl SkillOutcome {
+    /// Describes how the roll is judged (ex. `12 <= 60 / 5`).
+    pub fn describe(&self) -> String {
+        format!("{} {}", self.rolled_text, self.threshold)
+    }
+}
+
+/// Represents the rules of a game system to judge skill rolls.
+pub trait RuleSystem: Send + Sync {
+    /// Judges the tier of the rolled value.
+    fn judge(&self, chance: i32, rolled: i32) -> SkillTier;
+
+    /// Gets a label of the tier shown in the embeds.
+    fn label(&self, tier: SkillTier) -> &'static str {
+        tier.label()
+    }
+
+    /// Gets a text which explains the threshold of the tier.
+    fn threshold(&self, chance: i32, tier: SkillTier) -> String {
+        if tier.is_success() {
+            format!("<= {}", chance)
+        } else {
+            format!("> {}", chance)
+        }
+    }
+
+    /// Judges the rolled value and builds the outcome.
+    fn evaluate(&self, chance: i32, rolled: i32, rolled_text: String) -> SkillOutcome {
+        let tier = self.judge(chance, rolled);
+        SkillOutcome {
+            tier,
+            rolled,
+            rolled_text,
+            threshold: self.threshold(chance, tier),
+        }
+    }
+
+    /// Rolls a percentile dice and judges the result.
+    fn roll(&self, chance: i32, rng: &mut dyn RngCore) -> SkillOutcome {
+        let rolled = rng.gen_range(1..=100);
+        self.evaluate(chance, rolled, rolled.to_string())
+    }
+}
+
+/// The rules of Call of Cthulhu 6th Edition.
+pub struct Coc6th;
+
+/// The rules of Call of Cthulhu 7th Edition.
+pub struct Coc7th;
+
+/// The rules of Delta Green.
+pub struct DeltaGreen;
+
+/// The rules of the BRP 2023 rule book.
+pub struct Brp;
+
+/// A game system which a server plays.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameSystem {
+    Coc6th,
+    Coc7th,
+    DeltaGreen,
+    Brp,
+}
+
+/// All of the game systems. The first one is used by default.
+pub const ALL_SYSTEMS: [GameSystem; 4] = [
+    GameSystem::Coc6th,
+    GameSystem::Coc7th,
+    GameSystem::DeltaGreen,
+    GameSystem::Brp,
+];
+
+impl Default for GameSystem {
+    fn default() -> Self {
+        ALL_SYSTEMS[0]
+    }
+}
+
+impl GameSystem {
+    /// Gets an identifier used in the options and the database.
+    /// It is the same as the one of the profile of `/cs`.
+    pub fn id(self) -> &'static str {
+        match self {
+            GameSystem::Coc6th => "6th",
+            GameSystem::Coc7th => "7th",
+            GameSystem::DeltaGreen => "dg",
+            GameSystem::Brp => "brp",
+        }
+    }
+
+    /// Restores the system from the identifier.
+    pub fn from_id(id: &str) -> Option<Self> {
+        ALL_SYSTEMS.iter().find(|system| system.id() == id).copied()
+    }
+
+    /// Gets a name shown to the users.
+    pub fn name(self) -> &'static str {
+        match self {
+            GameSystem::Coc6th => "Call of Cthulhu 6th Edition",
+            GameSystem::Coc7th => "Call of Cthulhu 7th Edition",
+            GameSystem::DeltaGreen => "Delta Green",
+            GameSystem::Brp => "BRP 2023",
+        }
+    }
+
+    /// Gets the rules to judge skill rolls.
+    pub fn rule(self) -> &'static dyn RuleSystem {
+        match self {
+            GameSystem::Coc6th => &Coc6th,
+            GameSystem::Coc7th => &Coc7th,
+            GameSystem::DeltaGreen => &DeltaGreen,
+            GameSystem::Brp => &Brp,
+        }
+    }
+}
+
+impl RuleSystem for Coc6th {
+    fn judge(&self, chance: i32, rolled: i32) -> SkillTier {
+        match rolled {
+            1 if rolled <= chance => SkillTier::GreatCritical,
+            rolled if rolled <= 5 && rolled <= chance => SkillTier::Critical,
+            100 if rolled > chance => SkillTier::GreatFumble,
+            rolled if rolled > 95 && rolled > chance => SkillTier::Fumble,
+            rolled if rolled <= chance => SkillTier::Success,
+            _ => SkillTier::Failure,
+        }
+    }
+}
+
+impl RuleSystem for Coc7th {
+    fn judge(&self, chance: i32, rolled: i32) -> SkillTier {
+        match rolled {
+            1 if rolled <= chance => SkillTier::GreatCritical,
+            rolled if rolled <= chance / 5 => SkillTier::Extreme,
+            rolled if rolled <= chance / 2 => SkillTier::Hard,
+            rolled if rolled == 100 || (rolled > 95 && chance < 50) => SkillTier::Fumble,
+            rolled if rolled <= chance => SkillTier::Success,
+            _ => SkillTier::Failure,
+        }
+    }
+
+    fn threshold(&self, chance: i32, tier: SkillTier) -> String {
+        match tier {
+            SkillTier::Extreme => format!("<= {} / 5", chance),
+            SkillTier::Hard => format!("<= {} / 2", chance),
+            SkillTier::Fumble => format!(">= {}", chance),
+            tier if tier.is_success() => format!("<= {}", chance),
+            _ => format!("> {}", chance),
+        }
+    }
+}
+
+impl Coc7th {
+    /// Rolls a percentile dice with bonus (positive) or penalty (negative) dice and judges the result.
+    pub fn roll_with_bonus(&self, chance: i32, bonus: i32, rng: &mut dyn RngCore) -> SkillOutcome {
+        let (rolled, rolled_text) = roll_percentile(bonus, rng);
+        self.evaluate(chance, rolled, rolled_text)
+    }
+
+    /// Gets the maximum value which reaches the tier.
+    pub fn max_value(&self, chance: i32, tier: SkillTier) -> i32 {
+        match tier {
+            SkillTier::GreatCritical | SkillTier::Critical => 1,
+            SkillTier::Extreme => chance / 5,
+            SkillTier::Hard => chance / 2,
+            _ => chance,
+        }
+    }
+
+    /// Gets the tiers which can be reached by spending Luck.
+    pub fn luck_targets(&self, tier: SkillTier) -> &'static [SkillTier] {
+        match tier {
+            SkillTier::Failure => &[SkillTier::Success, SkillTier::Hard, SkillTier::Extreme],
+            SkillTier::Success => &[SkillTier::Hard, SkillTier::Extreme],
+            SkillTier::Hard => &[SkillTier::Extreme],
+            _ => &[],
+        }
+    }
+}
+
+impl RuleSystem for DeltaGreen {
+    fn judge(&self, chance: i32, rolled: i32) -> SkillTier {
+        match rolled {
+            1 if rolled <= chance => SkillTier::GreatCritical,
+            rolled if rolled <= 5 && rolled <= chance => SkillTier::Critical,
+            rolled if rolled / 10 == rolled % 10 && rolled <= chance => SkillTier::Critical,
+            100 if rolled > chance => SkillTier::GreatFumble,
+            rolled if rolled > 95 && rolled > chance => SkillTier::Fumble,
+            rolled if rolled <= chance => SkillTier::Success,
+            _ => SkillTier::Failure,
+        }
+    }
+}
+
+impl RuleSystem for Brp {
+    fn judge(&self, chance: i32, rolled: i32) -> SkillTier {
+        // A critical is 1/20 of the chance and a special is 1/5 of the chance, both rounded up.
+        // Note that a chance of 0 has neither of them.
+        let critical = (chance.max(0) + 19) / 20;
+        let special = (chance.max(0) + 4) / 5;
+        let fumble = i32::min(96 + (chance - 1).max(0) / 20, 100);
+
+        match rolled {
+            rolled if rolled <= critical => SkillTier::Critical,
+            rolled if rolled <= special => SkillTier::Special,
+            rolled if rolled >= fumble && rolled > chance => SkillTier::Fumble,
+            rolled if rolled <= chance => SkillTier::Success,
+            _ => SkillTier::Failure,
+        }
+    }
+
+    fn label(&self, tier: SkillTier) -> &'static str {
+        match tier {
+            SkillTier::Critical => SkillTier::GreatCritical.label(),
+            tier => tier.label(),
+        }
+    }
+}
+
+/// Compares the outcomes of an opposed roll following Delta Green and BRP.
+/// The better tier wins, and the higher roll wins between the same successes.
+/// Returns `Ordering::Greater` if the first one wins and `Ordering::Equal` if nobody wins.
+pub fn compare_opposed(first: &SkillOutcome, second: &SkillOutcome) -> Ordering {
+    match first.tier.rank().cmp(&second.tier.rank()) {
+        Ordering::Equal if first.tier.is_success() => first.rolled.cmp(&second.rolled),
+        Ordering::Equal => Ordering::Equal,
+        ordering => ordering,
+    }
+}
+
+/// Rolls a percentile dice with bonus (positive) or penalty (negative) dice.
+/// Returns the selected value and a text which describes how it is selected.
+pub fn roll_percentile(bonus: i32, rng: &mut dyn RngCore) -> (i32, String) {
+    let lower_digit = rng.gen_range(0..10);
+    let results = (0..(1 + bonus.abs()))
+        .map(|_| {
+            let res = rng.gen_range(0..10) * 10 + lower_digit;
+            if res == 0 {
+                100
+            } else {
+                res
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let list = results
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    match bonus.cmp(&0) {
+        Ordering::Equal => (results[0], results[0].to_string()),
+        Ordering::Greater => {
+            let minimum = *results.iter().min().unwrap();
+            (minimum, format!("min([{}]) = {}", list, minimum))
+        }
+        Ordering::Less => {
+            let maximum = *results.iter().max().unwrap();
+            (maximum, format!("max([{}]) = {}", list, maximum))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    /// Chances to be checked. It includes the values out of the usual range.
+    const CHANCES: std::ops::RangeInclusive<i32> = 0..=120;
+
+    #[test]
+    fn coc6th_all_rolls() {
+        for chance in CHANCES {
+            for rolled in 1..=100 {
+                let expected = if rolled == 1 && chance >= 1 {
+                    SkillTier::GreatCritical
+                } else if rolled <= 5 && rolled <= chance {
+                    SkillTier::Critical
+                } else if rolled == 100 && chance < 100 {
+                    SkillTier::GreatFumble
+                } else if rolled >= 96 && rolled > chance {
+                    SkillTier::Fumble
+                } else if rolled <= chance {
+                    SkillTier::Success
+                } else {
+                    SkillTier::Failure
+                };
+                assert_eq!(
+                    Coc6th.judge(chance, rolled),
+                    expected,
+                    "chance: {}, rolled: {}",
+                    chance,
+                    rolled
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn coc7th_all_rolls() {
+        for chance in CHANCES {
+            for rolled in 1..=100 {
+                let expected = if rolled == 1 && chance >= 1 {
+                    SkillTier::GreatCritical
+                } else if rolled * 5 <= chance - chance % 5 {
+                    SkillTier::Extreme
+                } else if rolled * 2 <= chance - chance % 2 {
+                    SkillTier::Hard
+                } else if rolled == 100 || (rolled >= 96 && chance < 50) {
+                    SkillTier::Fumble
+                } else if rolled <= chance {
+                    SkillTier::Success
+                } else {
+                    SkillTier::Failure
+                };
+                assert_eq!(
+                    Coc7th.judge(chance, rolled),
+                    expected,
+                    "chance: {}, rolled: {}",
+                    chance,
+                    rolled
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn coc7th_boundaries() {
+        assert_eq!(Coc7th.judge(60, 12), SkillTier::Extreme);
+        assert_eq!(Coc7th.judge(60, 13), SkillTier::Hard);
+        assert_eq!(Coc7th.judge(60, 30), SkillTier::Hard);
+        assert_eq!(Coc7th.judge(60, 31), SkillTier::Success);
+        assert_eq!(Coc7th.judge(60, 61), SkillTier::Failure);
+        assert_eq!(Coc7th.judge(49, 96), SkillTier::Fumble);
+        assert_eq!(Coc7th.judge(50, 96), SkillTier::Failure);
+        assert_eq!(Coc7th.judge(100, 100), SkillTier::Fumble);
+    }
+
+    #[test]
+    fn delta_green_all_rolls() {
+        for chance in CHANCES {
+            for rolled in 1..=100 {
+                let is_double = rolled < 100 && rolled / 10 == rolled % 10;
+                let expected = if rolled == 1 && chance >= 1 {
+                    SkillTier::GreatCritical
+                } else if (rolled <= 5 || is_double) && rolled <= chance {
+                    SkillTier::Critical
+                } else if rolled == 100 && chance < 100 {
+                    SkillTier::GreatFumble
+                } else if rolled >= 96 && rolled > chance {
+                    SkillTier::Fumble
+                } else if rolled <= chance {
+                    SkillTier::Success
+                } else {
+                    SkillTier::Failure
+                };
+                assert_eq!(
+                    DeltaGreen.judge(chance, rolled),
+                    expected,
+                    "chance: {}, rolled: {}",
+                    chance,
+                    rolled
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn brp_all_rolls() {
+        for chance in CHANCES {
+            for rolled in 1..=100 {
+                // Compare in the real numbers: rolled <= ceil(chance / 20) <=> 20 * (rolled - 1) < chance.
+                let expected = if 20 * (rolled - 1) < chance {
+                    SkillTier::Critical
+                } else if 5 * (rolled - 1) < chance {
+                    SkillTier::Special
+                } else if rolled > chance && rolled >= 96 + (chance - 1).max(0) / 20 {
+                    SkillTier::Fumble
+                } else if rolled <= chance {
+                    SkillTier::Success
+                } else {
+                    SkillTier::Failure
+                };
+                assert_eq!(
+                    Brp.judge(chance, rolled),
+                    expected,
+                    "chance: {}, rolled: {}",
+                    chance,
+                    rolled
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn brp_boundaries() {
+        // A chance of 0 can never succeed.
+        assert_eq!(Brp.judge(0, 1), SkillTier::Failure);
+        assert_eq!(Brp.judge(1, 1), SkillTier::Critical);
+        assert_eq!(Brp.judge(20, 1), SkillTier::Critical);
+        assert_eq!(Brp.judge(20, 2), SkillTier::Special);
+        assert_eq!(Brp.judge(21, 2), SkillTier::Critical);
+        assert_eq!(Brp.judge(25, 5), SkillTier::Special);
+        assert_eq!(Brp.judge(25, 6), SkillTier::Success);
+        assert_eq!(Brp.judge(26, 6), SkillTier::Special);
+        assert_eq!(Brp.judge(20, 95), SkillTier::Failure);
+        assert_eq!(Brp.judge(20, 96), SkillTier::Fumble);
+        assert_eq!(Brp.judge(21, 96), SkillTier::Failure);
+        assert_eq!(Brp.judge(21, 97), SkillTier::Fumble);
+        assert_eq!(Brp.judge(99, 100), SkillTier::Fumble);
+    }
+
+    #[test]
+    fn tier_ids_round_trip() {
+        for tier in ALL_TIERS {
+            assert_eq!(SkillTier::from_id(tier.id()), Some(tier));
+        }
+        assert!(SkillTier::GreatCritical.rank() > SkillTier::Critical.rank());
+        assert!(SkillTier::Success.rank() > SkillTier::Failure.rank());
+    }
+
+    #[test]
+    fn roll_with_injected_rng() {
+        let first = Coc7th.roll(50, &mut StdRng::seed_from_u64(42));
+        let second = Coc7th.roll(50, &mut StdRng::seed_from_u64(42));
+        assert_eq!(first, second);
+        assert_eq!(first.tier, Coc7th.judge(50, first.rolled));
+    }
+
+    #[test]
+    fn percentile_stays_in_range() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for bonus in -2..=2 {
+            for _ in 0..1000 {
+                let (rolled, _) = roll_percentile(bonus, &mut rng);
+                assert!((1..=100).contains(&rolled));
+            }
+        }
+    }
+
+    #[test]
+    fn bonus_dice_select_the_best() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..1000 {
+            let outcome = Coc7th.roll_with_bonus(50, 2, &mut rng);
+            assert!(outcome.rolled_text.starts_with("min(["));
+            assert!(outcome
+                .rolled_text
+                .ends_with(&format!("= {}", outcome.rolled)));
+        }
+    }
+
+    #[test]
+    fn system_ids_round_trip() {
+        for system in ALL_SYSTEMS.iter() {
+            assert_eq!(GameSystem::from_id(system.id()), Some(*system));
+        }
+        assert_eq!(GameSystem::from_id("5th"), None);
+        assert_eq!(GameSystem::default(), GameSystem::Coc6th);
+    }
+
+    #[test]
+    fn opposed_rolls() {
+        let outcome = |chance, rolled| DeltaGreen.evaluate(chance, rolled, rolled.to_string());
+
+        // A success beats a failure.
+        assert_eq!(
+            compare_opposed(&outcome(50, 40), &outcome(50, 60)),
+            Ordering::Greater
+        );
+        // The higher roll wins between the successes.
+        assert_eq!(
+            compare_opposed(&outcome(50, 20), &outcome(70, 45)),
+            Ordering::Less
+        );
+        // A critical beats a higher success.
+        assert_eq!(
+            compare_opposed(&outcome(50, 11), &outcome(50, 45)),
+            Ordering::Greater
+        );
+        // Nobody wins when both fail.
+        assert_eq!(
+            compare_opposed(&outcome(30, 50), &outcome(30, 90)),
+            Ordering::Equal
+        );
+    }
+}
@@ -0,0 +1,160 @@
+/// The standard skills of Call of Cthulhu 6th and 7th Edition in English and Japanese.
+pub const STANDARD_SKILLS: [(&str, &str); 66] = [
+    ("Accounting", "経理"),
+    ("Anthropology", "人類学"),
+    ("Appraise", "鑑定"),
+    ("Archaeology", "考古学"),
+    ("Art/Craft", "芸術/製作"),
+    ("Astronomy", "天文学"),
+    ("Bargain", "値切り"),
+    ("Biology", "生物学"),
+    ("Charm", "魅惑"),
+    ("Chemistry", "化学"),
+    ("Climb", "登攀"),
+    ("Computer Use", "コンピューター"),
+    ("Conceal", "隠す"),
+    ("Credit Rating", "信用"),
+    ("Cthulhu Mythos", "クトゥルフ神話"),
+    ("Disguise", "変装"),
+    ("Dodge", "回避"),
+    ("Drive Auto", "運転"),
+    ("Electrical Repair", "電気修理"),
+    ("Electronics", "電子工学"),
+    ("Fast Talk", "言いくるめ"),
+    ("Fighting (Brawl)", "近接戦闘(格闘)"),
+    ("Firearms (Handgun)", "射撃(拳銃)"),
+    ("Firearms (Rifle/Shotgun)", "射撃(ライフル/ショットガン)"),
+    ("First Aid", "応急手当"),
+    ("Fist/Punch", "こぶし(パンチ)"),
+    ("Geology", "地質学"),
+    ("Grapple", "組み付き"),
+    ("Head Butt", "頭突き"),
+    ("Hide", "隠れる"),
+    ("History", "歴史"),
+    ("Idea", "アイデア"),
+    ("Intimidate", "威圧"),
+    ("Jump", "跳躍"),
+    ("Kick", "キック"),
+    ("Know", "知識"),
+    ("Language (Other)", "ほかの言語"),
+    ("Language (Own)", "母国語"),
+    ("Law", "法律"),
+    ("Library Use", "図書館"),
+    ("Listen", "聞き耳"),
+    ("Locksmith", "鍵開け"),
+    ("Luck", "幸運"),
+    ("Martial Arts", "マーシャルアーツ"),
+    ("Mechanical Repair", "機械修理"),
+    ("Medicine", "医学"),
+    ("Natural World", "博物学"),
+    ("Navigate", "ナビゲート"),
+    ("Occult", "オカルト"),
+    ("Operate Heavy Machinery", "重機械操作"),
+    ("Persuade", "説得"),
+    ("Pharmacy", "薬学"),
+    ("Photography", "写真術"),
+    ("Physics", "物理学"),
+    ("Pilot", "操縦"),
+    ("Psychoanalysis", "精神分析"),
+    ("Psychology", "心理学"),
+    ("Ride", "乗馬"),
+    ("SAN", "正気度"),
+    ("Science", "科学"),
+    ("Sleight of Hand", "手さばき"),
+    ("Sneak", "忍び歩き"),
+    ("Spot Hidden", "目星"),
+    ("Stealth", "隠密"),
+    ("Swim", "水泳"),
+    ("Track", "追跡"),
+];
+
+/// Other names of the standard skills found on the character sheets (ex. `SAN Check`).
+const SKILL_ALIASES: [(&str, &str); 6] = [
+    ("SAN Check", "SAN"),
+    ("Sanity", "SAN"),
+    ("SANチェック", "SAN"),
+    ("正気度ロール", "SAN"),
+    ("LUCK", "Luck"),
+    ("Luck Roll", "Luck"),
+];
+
+/// Finds the standard skill (in English) which the name of a skill or a characteristic stands for.
+pub fn standard_skill(name: &str) -> Option<&'static str> {
+    let name = name.trim();
+    let standard = STANDARD_SKILLS
+        .iter()
+        .find(|(en, ja)| en.eq_ignore_ascii_case(name) || *ja == name)
+        .map(|(en, _)| *en);
+    standard.or_else(|| {
+        SKILL_ALIASES
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+            .map(|(_, standard)| *standard)
+    })
+}
+
+/// The maximum number of suggestions Discord accepts.
+pub const MAX_SUGGESTIONS: usize = 25;
+
+/// Suggests skill names which contain the typed text.
+/// The skills of the character come first, followed by the standard skills in the language typed.
+pub fn suggest_skills(character_skills: &[&str], typed: &str) -> Vec<String> {
+    let typed = typed.trim().to_lowercase();
+    let is_japanese = !typed.is_ascii();
+
+    let standard = STANDARD_SKILLS
+        .iter()
+        .map(|(en, ja)| if is_japanese { *ja } else { *en });
+
+    let mut suggestions: Vec<String> = Vec::new();
+    for name in character_skills.iter().copied().chain(standard) {
+        if suggestions.len() >= MAX_SUGGESTIONS {
+            break;
+        }
+        if !name.to_lowercase().contains(&typed) {
+            continue;
+        }
+        if suggestions
+            .iter()
+            .any(|suggested| suggested.eq_ignore_ascii_case(name))
+        {
+            continue;
+        }
+        suggestions.push(name.to_string());
+    }
+    suggestions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn character_skills_come_first() {
+        let suggestions = suggest_skills(&["Spot Hidden", "Drive Carriage"], "");
+        assert_eq!(suggestions.len(), MAX_SUGGESTIONS);
+        assert_eq!(suggestions[0], "Spot Hidden");
+        assert_eq!(suggestions[1], "Drive Carriage");
+
+        // The standard one is not duplicated.
+        let suggestions = suggest_skills(&["spot hidden"], "spot");
+        assert_eq!(suggestions, vec!["spot hidden"]);
+    }
+
+    #[test]
+    fn matches_the_typed_language() {
+        assert_eq!(suggest_skills(&[], "LIB"), vec!["Library Use"]);
+        assert_eq!(suggest_skills(&[], "目"), vec!["目星"]);
+        assert!(suggest_skills(&[], "射撃").len() == 2);
+        assert!(suggest_skills(&[], "xyz").is_empty());
+    }
+
+    #[test]
+    fn find_standard_skills() {
+        assert_eq!(standard_skill("spot hidden"), Some("Spot Hidden"));
+        assert_eq!(standard_skill("幸運"), Some("Luck"));
+        assert_eq!(standard_skill("正気度"), Some("SAN"));
+        assert_eq!(standard_skill("san check"), Some("SAN"));
+        assert_eq!(standard_skill("Drive Carriage"), None);
+    }
+}
@@ -0,0 +1,371 @@
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use serenity::model::id::UserId;
+
+/// Represents what a value of the character stands for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValueKind {
+    Characteristic,
+    Skill,
+}
+
+impl ValueKind {
+    /// Gets an identifier stored in the database.
+    pub fn id(self) -> &'static str {
+        match self {
+            ValueKind::Characteristic => "characteristic",
+            ValueKind::Skill => "skill",
+        }
+    }
+
+    /// Restores the kind from the identifier.
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "characteristic" => Some(ValueKind::Characteristic),
+            "skill" => Some(ValueKind::Skill),
+            _ => None,
+        }
+    }
+}
+
+/// A character owned by a Discord user.
+#[derive(Clone, Debug)]
+pub struct Character {
+    pub id: i64,
+    pub owner_id: UserId,
+    pub name: String,
+    /// An identifier of the profile the character was generated with (ex. `7th`).
+    pub system: String,
+    pub characteristics: Vec<(String, i32)>,
+    pub skills: Vec<(String, i32)>,
+}
+
+impl Character {
+    /// Saves a new character and makes it the active one of the owner.
+    /// Returns `None` if the owner already has a character with the same name.
+    pub fn insert(
+        connection: &mut Connection,
+        owner_id: UserId,
+        name: &str,
+        system: &str,
+        characteristics: &[(&str, i32)],
+        skills: &[(&str, i32)],
+    ) -> Result<Option<Character>> {
+        let transaction = connection.transaction()?;
+
+        let inserted = transaction.execute(
+            "INSERT OR IGNORE INTO characters (owner_id, name, system) VALUES (?1, ?2, ?3)",
+            params![owner_id.get() as i64, name, system],
+        )?;
+        if inserted == 0 {
+            return Ok(None);
+        }
+        let id = transaction.last_insert_rowid();
+
+        let values = characteristics
+            .iter()
+            .map(|value| (ValueKind::Characteristic, value))
+            .chain(skills.iter().map(|value| (ValueKind::Skill, value)));
+        for (kind, (name, value)) in values {
+            transaction.execute(
+                "INSERT INTO character_values (character_id, kind, name, value) VALUES (?1, ?2, ?3, ?4)",
+                params![id, kind.id(), name, value],
+            )?;
+        }
+        Character::activate_in(&transaction, owner_id, id)?;
+
+        transaction.commit()?;
+
+        Character::load(connection, id)
+    }
+
+    /// Finds a character of the owner by its name. The name is case-insensitive.
+    pub fn find(
+        connection: &Connection,
+        owner_id: UserId,
+        name: &str,
+    ) -> Result<Option<Character>> {
+        let id = connection
+            .query_row(
+                "SELECT id FROM characters WHERE owner_id = ?1 AND name = ?2",
+                params![owner_id.get() as i64, name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match id {
+            Some(id) => Character::load(connection, id),
+            None => Ok(None),
+        }
+    }
+
+    /// Gets the active character of the owner.
+    pub fn active(connection: &Connection, owner_id: UserId) -> Result<Option<Character>> {
+        let id = connection
+            .query_row(
+                "SELECT id FROM characters WHERE owner_id = ?1 AND active = 1",
+                params![owner_id.get() as i64],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match id {
+            Some(id) => Character::load(connection, id),
+            None => Ok(None),
+        }
+    }
+
+    /// Lists the names of the characters the owner has with whether each one is active.
+    pub fn list(connection: &Connection, owner_id: UserId) -> Result<Vec<(String, bool)>> {
+        let mut statement = connection
+            .prepare("SELECT name, active FROM characters WHERE owner_id = ?1 ORDER BY id")?;
+        let names = statement
+            .query_map(params![owner_id.get() as i64], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(names)
+    }
+
+    /// Makes the character the active one of the owner.
+    /// Returns `false` if the owner does not have the character.
+    pub fn activate(connection: &mut Connection, owner_id: UserId, name: &str) -> Result<bool> {
+        let transaction = connection.transaction()?;
+
+        let id: Option<i64> = transaction
+            .query_row(
+                "SELECT id FROM characters WHERE owner_id = ?1 AND name = ?2",
+                params![owner_id.get() as i64, name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let Some(id) = id else {
+            return Ok(false);
+        };
+        Character::activate_in(&transaction, owner_id, id)?;
+
+        transaction.commit()?;
+
+        Ok(true)
+    }
+
+    /// Deletes the character.
+    /// Returns `false` if the owner does not have the character.
+    pub fn delete(connection: &Connection, owner_id: UserId, name: &str) -> Result<bool> {
+        let deleted = connection.execute(
+            "DELETE FROM characters WHERE owner_id = ?1 AND name = ?2",
+            params![owner_id.get() as i64, name],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    /// Sets a value of the character.
+    /// An existing characteristic or skill is updated, otherwise a new skill is added.
+    pub fn set_value(
+        &mut self,
+        connection: &Connection,
+        name: &str,
+        value: i32,
+    ) -> Result<ValueKind> {
+        let kind = if self.get_characteristic(name).is_some() {
+            ValueKind::Characteristic
+        } else {
+            ValueKind::Skill
+        };
+
+        connection.execute(
+            "INSERT INTO character_values (character_id, kind, name, value) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (character_id, name) DO UPDATE SET value = excluded.value",
+            params![self.id, kind.id(), name, value],
+        )?;
+
+        let values = match kind {
+            ValueKind::Characteristic => &mut self.characteristics,
+            ValueKind::Skill => &mut self.skills,
+        };
+        match values
+            .iter_mut()
+            .find(|(entry, _)| entry.eq_ignore_ascii_case(name))
+        {
+            Some(entry) => entry.1 = value,
+            None => values.push((name.to_string(), value)),
+        }
+
+        Ok(kind)
+    }
+
+    /// Gets a characteristic by its name. The name is case-insensitive.
+    pub fn get_characteristic(&self, name: &str) -> Option<i32> {
+        Character::lookup(&self.characteristics, name)
+    }
+
+    /// Gets a skill by its name. The name is case-insensitive.
+    pub fn get_skill(&self, name: &str) -> Option<i32> {
+        Character::lookup(&self.skills, name)
+    }
+
+    fn lookup(values: &[(String, i32)], name: &str) -> Option<i32> {
+        values
+            .iter()
+            .find(|(entry, _)| entry.eq_ignore_ascii_case(name))
+            .map(|(_, value)| *value)
+    }
+
+    /// Loads the character and its values.
+    fn load(connection: &Connection, id: i64) -> Result<Option<Character>> {
+        let character = connection
+            .query_row(
+                "SELECT owner_id, name, system FROM characters WHERE id = ?1",
+                params![id],
+                |row| {
+                    Ok(Character {
+                        id,
+                        owner_id: UserId::new(row.get::<_, i64>(0)? as u64),
+                        name: row.get(1)?,
+                        system: row.get(2)?,
+                        characteristics: Vec::new(),
+                        skills: Vec::new(),
+                    })
+                },
+            )
+            .optional()?;
+        let Some(mut character) = character else {
+            return Ok(None);
+        };
+
+        let mut statement = connection.prepare(
+            "SELECT kind, name, value FROM character_values WHERE character_id = ?1 ORDER BY rowid",
+        )?;
+        let mut rows = statement.query(params![id])?;
+        while let Some(row) = rows.next()? {
+            let kind: String = row.get(0)?;
+            let entry = (row.get(1)?, row.get(2)?);
+            match ValueKind::from_id(&kind) {
+                Some(ValueKind::Characteristic) => character.characteristics.push(entry),
+                Some(ValueKind::Skill) => character.skills.push(entry),
+                None => {}
+            }
+        }
+
+        Ok(Some(character))
+    }
+
+    /// Makes the character the only active one of the owner.
+    fn activate_in(connection: &Connection, owner_id: UserId, id: i64) -> Result<()> {
+        connection.execute(
+            "UPDATE characters SET active = (id = ?2) WHERE owner_id = ?1",
+            params![owner_id.get() as i64, id],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::open_in_memory;
+
+    const OWNER: UserId = UserId::new(1);
+
+    #[test]
+    fn insert_and_find() {
+        let mut connection = open_in_memory();
+
+        let character = Character::insert(
+            &mut connection,
+            OWNER,
+            "Harvey Walters",
+            "7th",
+            &[("STR", 50), ("EDU", 80)],
+            &[("Spot Hidden", 25)],
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(character.get_characteristic("edu"), Some(80));
+
+        let found = Character::find(&connection, OWNER, "harvey walters")
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.id, character.id);
+        assert_eq!(found.system, "7th");
+        assert_eq!(
+            found.characteristics,
+            vec![("STR".to_string(), 50), ("EDU".to_string(), 80)]
+        );
+        assert_eq!(found.get_skill("spot hidden"), Some(25));
+
+        // The name must be unique for each owner.
+        assert!(
+            Character::insert(&mut connection, OWNER, "Harvey Walters", "6th", &[], &[])
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            Character::find(&connection, UserId::new(2), "Harvey Walters")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn the_last_one_is_active() {
+        let mut connection = open_in_memory();
+
+        Character::insert(&mut connection, OWNER, "A", "6th", &[], &[]).unwrap();
+        Character::insert(&mut connection, OWNER, "B", "6th", &[], &[]).unwrap();
+        let active = Character::active(&connection, OWNER).unwrap().unwrap();
+        assert_eq!(active.name, "B");
+
+        assert!(Character::activate(&mut connection, OWNER, "a").unwrap());
+        assert!(!Character::activate(&mut connection, OWNER, "C").unwrap());
+        assert_eq!(
+            Character::list(&connection, OWNER).unwrap(),
+            vec![("A".to_string(), true), ("B".to_string(), false)]
+        );
+    }
+
+    #[test]
+    fn set_values() {
+        let mut connection = open_in_memory();
+
+        let mut character =
+            Character::insert(&mut connection, OWNER, "A", "6th", &[("STR", 10)], &[])
+                .unwrap()
+                .unwrap();
+        assert_eq!(
+            character.set_value(&connection, "str", 12).unwrap(),
+            ValueKind::Characteristic
+        );
+        assert_eq!(
+            character.set_value(&connection, "Spot Hidden", 60).unwrap(),
+            ValueKind::Skill
+        );
+        assert_eq!(
+            character.set_value(&connection, "spot hidden", 65).unwrap(),
+            ValueKind::Skill
+        );
+
+        let found = Character::find(&connection, OWNER, "A").unwrap().unwrap();
+        assert_eq!(found.characteristics, vec![("STR".to_string(), 12)]);
+        assert_eq!(found.skills, vec![("Spot Hidden".to_string(), 65)]);
+    }
+
+    #[test]
+    fn delete_removes_values() {
+        let mut connection = open_in_memory();
+
+        let mut character =
+            Character::insert(&mut connection, OWNER, "A", "6th", &[("STR", 10)], &[])
+                .unwrap()
+                .unwrap();
+        character.set_value(&connection, "Listen", 50).unwrap();
+
+        assert!(Character::delete(&connection, OWNER, "A").unwrap());
+        assert!(!Character::delete(&connection, OWNER, "A").unwrap());
+
+        let count: i64 = connection
+            .query_row("SELECT COUNT(*) FROM character_values", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(count, 0);
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use serenity::model::id::{ChannelId, MessageId, UserId};
+
+use crate::combat::Combat;
+
+/// A combat running in a channel with the message which shows it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CombatTracker {
+    pub channel_id: ChannelId,
+    /// The keeper who started the combat.
+    pub owner_id: UserId,
+    /// The message of the tracker, which is edited in place.
+    pub message_id: MessageId,
+    pub combat: Combat,
+}
+
+impl CombatTracker {
+    /// Saves the tracker. The one in the same channel is overwritten.
+    pub fn save(&self, connection: &Connection) -> Result<()> {
+        connection.execute(
+            "INSERT INTO combats (channel_id, owner_id, message_id, state) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (channel_id) DO UPDATE SET
+                owner_id = excluded.owner_id,
+                message_id = excluded.message_id,
+                state = excluded.state",
+            params![
+                self.channel_id.get() as i64,
+                self.owner_id.get() as i64,
+                self.message_id.get() as i64,
+                serde_json::to_string(&self.combat)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Finds the combat running in the channel.
+    pub fn find(connection: &Connection, channel_id: ChannelId) -> Result<Option<CombatTracker>> {
+        let row = connection
+            .query_row(
+                "SELECT owner_id, message_id, state FROM combats WHERE channel_id = ?1",
+                params![channel_id.get() as i64],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        match row {
+            Some((owner_id, message_id, state)) => Ok(Some(CombatTracker {
+                channel_id,
+                owner_id: UserId::new(owner_id as u64),
+                message_id: MessageId::new(message_id as u64),
+                combat: serde_json::from_str(&state)?,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// Deletes the combat running in the channel.
+    /// Returns `false` if nothing is running there.
+    pub fn delete(connection: &Connection, channel_id: ChannelId) -> Result<bool> {
+        let deleted = connection.execute(
+            "DELETE FROM combats WHERE channel_id = ?1",
+            params![channel_id.get() as i64],
+        )?;
+        Ok(deleted > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::combat::Participant;
+    use crate::storage::open_in_memory;
+
+    #[test]
+    fn save_and_delete() {
+        let connection = open_in_memory();
+        let channel_id = ChannelId::new(1);
+
+        let mut tracker = CombatTracker {
+            channel_id,
+            owner_id: UserId::new(2),
+            message_id: MessageId::new(3),
+            combat: Combat::default(),
+        };
+        tracker.save(&connection).unwrap();
+
+        tracker
+            .combat
+            .add(Participant::new("Harvey", 40, true, false))
+            .unwrap();
+        tracker.combat.next_turn();
+        tracker.save(&connection).unwrap();
+
+        assert_eq!(
+            CombatTracker::find(&connection, channel_id).unwrap(),
+            Some(tracker)
+        );
+
+        assert!(CombatTracker::delete(&connection, channel_id).unwrap());
+        assert!(!CombatTracker::delete(&connection, channel_id).unwrap());
+        assert!(CombatTracker::find(&connection, channel_id)
+            .unwrap()
+            .is_none());
+    }
+}
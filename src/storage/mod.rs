@@ -0,0 +1,246 @@
+use std::env;
+
+use anyhow::Result;
+use log::info;
+use once_cell::sync::OnceCell;
+use rusqlite::Connection;
+use serenity::prelude::Mutex;
+
+use crate::config::{BotConfig, BotConfigError};
+
+/// The database used when `database_url` is not specified.
+/// It is placed in the directory where this bot is placed.
+const DEFAULT_DATABASE_FILE: &str = "cthulhu_bot.db";
+
+/// The schema of the database. Each entry is applied once, in order, on startup.
+/// Never edit an entry already released; append a new one instead.
+const MIGRATIONS: &[&str] = &[
+    // 1: Characters.
+    "
+    CREATE TABLE characters (
+        id INTEGER PRIMARY KEY,
+        owner_id INTEGER NOT NULL,
+        name TEXT NOT NULL COLLATE NOCASE,
+        system TEXT NOT NULL,
+        active INTEGER NOT NULL DEFAULT 0,
+        UNIQUE (owner_id, name)
+    );
+    CREATE TABLE character_values (
+        character_id INTEGER NOT NULL REFERENCES characters (id) ON DELETE CASCADE,
+        kind TEXT NOT NULL,
+        name TEXT NOT NULL COLLATE NOCASE,
+        value INTEGER NOT NULL,
+        PRIMARY KEY (character_id, name)
+    );
+    ",
+    // 2: Secret rolls.
+    "
+    CREATE TABLE secret_rolls (
+        id INTEGER PRIMARY KEY,
+        owner_id INTEGER NOT NULL,
+        embed TEXT NOT NULL,
+        revealed INTEGER NOT NULL DEFAULT 0
+    );
+    ",
+    // 3: Settings of the guilds.
+    "
+    CREATE TABLE guild_settings (
+        guild_id INTEGER PRIMARY KEY,
+        system TEXT NOT NULL
+    );
+    ",
+    // 4: Session logs.
+    "
+    CREATE TABLE sessions (
+        id INTEGER PRIMARY KEY,
+        guild_id INTEGER,
+        channel_id INTEGER NOT NULL,
+        started_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        ended_at TEXT
+    );
+    CREATE UNIQUE INDEX running_sessions ON sessions (channel_id) WHERE ended_at IS NULL;
+    CREATE TABLE session_rolls (
+        id INTEGER PRIMARY KEY,
+        session_id INTEGER NOT NULL REFERENCES sessions (id) ON DELETE CASCADE,
+        rolled_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+        user_id INTEGER NOT NULL,
+        command TEXT NOT NULL,
+        secret INTEGER NOT NULL,
+        roller TEXT NOT NULL,
+        expression TEXT NOT NULL,
+        dice TEXT NOT NULL,
+        result TEXT NOT NULL,
+        tier TEXT
+    );
+    ",
+    // 5: Statistics of the skill rolls.
+    "
+    CREATE TABLE roll_stats (
+        id INTEGER PRIMARY KEY,
+        guild_id INTEGER,
+        user_id INTEGER NOT NULL,
+        system TEXT NOT NULL,
+        tier TEXT NOT NULL,
+        rolled INTEGER NOT NULL,
+        bonus INTEGER NOT NULL DEFAULT 0,
+        rolled_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    );
+    CREATE INDEX roll_stats_users ON roll_stats (guild_id, user_id);
+    ",
+    // 6: Combats.
+    "
+    CREATE TABLE combats (
+        channel_id INTEGER PRIMARY KEY,
+        owner_id INTEGER NOT NULL,
+        message_id INTEGER NOT NULL,
+        state TEXT NOT NULL
+    );
+    ",
+    // 7: HP, MP and SAN of the characters.
+    "
+    CREATE TABLE character_vitals (
+        character_id INTEGER PRIMARY KEY REFERENCES characters (id) ON DELETE CASCADE,
+        hp INTEGER,
+        mp INTEGER,
+        san INTEGER,
+        major_wound INTEGER NOT NULL DEFAULT 0,
+        san_day_start INTEGER,
+        san_lost_today INTEGER NOT NULL DEFAULT 0
+    );
+    ",
+    // 8: Random tables of the guilds.
+    "
+    CREATE TABLE random_tables (
+        guild_id INTEGER NOT NULL,
+        name TEXT NOT NULL COLLATE NOCASE,
+        owner_id INTEGER NOT NULL,
+        content TEXT NOT NULL,
+        PRIMARY KEY (guild_id, name)
+    );
+    ",
+    // 9: Dice seeds of the guilds.
+    "
+    CREATE TABLE guild_seeds (
+        guild_id INTEGER PRIMARY KEY,
+        seed INTEGER NOT NULL,
+        position INTEGER NOT NULL DEFAULT 0
+    );
+    ",
+];
+
+/// Holds the connection to the database. You need to call `Storage::init` before using this.
+static CONNECTION: OnceCell<Mutex<Connection>> = OnceCell::new();
+
+/// A persistent storage of the bot.
+pub struct Storage;
+
+impl Storage {
+    /// Opens the database specified by `database_url` and applies the migrations.
+    pub fn init() -> Result<()> {
+        let config = BotConfig::get();
+
+        let url = match &config.database_url {
+            Some(url) => url.clone(),
+            None => {
+                let executable_path = env::current_exe()?;
+                let executable_dir = executable_path.parent().ok_or(BotConfigError::new(
+                    "Cannot retrieve the parent of this executable.",
+                ))?;
+                executable_dir
+                    .join(DEFAULT_DATABASE_FILE)
+                    .to_string_lossy()
+                    .to_string()
+            }
+        };
+
+        let mut connection = Storage::open(&url)?;
+        Storage::migrate(&mut connection)?;
+
+        if CONNECTION.set(Mutex::new(connection)).is_err() {
+            panic!("Re-initialized the storage.");
+        }
+
+        Ok(())
+    }
+
+    /// Opens a database from the URL. Only SQLite (`sqlite://<path>` or a plain path) is supported.
+    pub fn open(url: &str) -> Result<Connection> {
+        let path = match url.strip_prefix("sqlite://") {
+            Some(path) => path,
+            None if url.contains("://") => Err(BotConfigError::new(
+                "Only SQLite is supported as the database.",
+            ))?,
+            None => url,
+        };
+
+        let connection = Connection::open(path)?;
+        connection.pragma_update(None, "foreign_keys", true)?;
+        Ok(connection)
+    }
+
+    /// Applies the migrations which have not been applied yet.
+    /// The number of the applied ones is tracked by `PRAGMA user_version`.
+    pub fn migrate(connection: &mut Connection) -> Result<()> {
+        let version: usize =
+            connection.pragma_query_value(None, "user_version", |row| row.get(0))?;
+
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let transaction = connection.transaction()?;
+            transaction.execute_batch(migration)?;
+            transaction.pragma_update(None, "user_version", index + 1)?;
+            transaction.commit()?;
+
+            info!("Applied the migration {}.", index + 1);
+        }
+
+        Ok(())
+    }
+
+    /// Runs `f` with the connection to the database.
+    pub async fn with<T>(f: impl FnOnce(&mut Connection) -> Result<T>) -> Result<T> {
+        let connection = CONNECTION.get().expect("The storage is not initialized.");
+        let mut connection = connection.lock().await;
+        f(&mut connection)
+    }
+}
+
+/// Opens an empty database for the tests.
+#[cfg(test)]
+pub fn open_in_memory() -> Connection {
+    let mut connection = Storage::open(":memory:").unwrap();
+    Storage::migrate(&mut connection).unwrap();
+    connection
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrations_are_applied_once() {
+        let mut connection = open_in_memory();
+        let version: usize = connection
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+
+        // Applying them again must be a no-op.
+        Storage::migrate(&mut connection).unwrap();
+    }
+
+    #[test]
+    fn only_sqlite_is_supported() {
+        assert!(Storage::open("sqlite://:memory:").is_ok());
+        assert!(Storage::open("postgres://localhost/cthulhu").is_err());
+    }
+}
+
+pub mod character;
+pub mod combat;
+pub mod random_table;
+pub mod secret;
+pub mod seed;
+pub mod session;
+pub mod settings;
+pub mod stats;
+pub mod vitals;
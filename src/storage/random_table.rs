@@ -0,0 +1,146 @@
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use serenity::model::id::{GuildId, UserId};
+
+use crate::random_table::RandomTable;
+
+/// A random table saved in a guild under a name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoredTable {
+    pub guild_id: GuildId,
+    pub name: String,
+    /// The user who uploaded the table.
+    pub owner_id: UserId,
+    pub table: RandomTable,
+}
+
+impl StoredTable {
+    /// Saves the table. The one with the same name in the guild is overwritten.
+    pub fn save(&self, connection: &Connection) -> Result<()> {
+        connection.execute(
+            "INSERT INTO random_tables (guild_id, name, owner_id, content) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (guild_id, name) DO UPDATE SET
+                name = excluded.name,
+                owner_id = excluded.owner_id,
+                content = excluded.content",
+            params![
+                self.guild_id.get() as i64,
+                self.name,
+                self.owner_id.get() as i64,
+                serde_json::to_string(&self.table)?
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Finds the table in the guild. The name is case-insensitive.
+    pub fn find(
+        connection: &Connection,
+        guild_id: GuildId,
+        name: &str,
+    ) -> Result<Option<StoredTable>> {
+        let row = connection
+            .query_row(
+                "SELECT name, owner_id, content FROM random_tables WHERE guild_id = ?1 AND name = ?2",
+                params![guild_id.get() as i64, name],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        match row {
+            Some((name, owner_id, content)) => Ok(Some(StoredTable {
+                guild_id,
+                name,
+                owner_id: UserId::new(owner_id as u64),
+                table: serde_json::from_str(&content)?,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    /// Lists the tables in the guild sorted by their names.
+    pub fn list(connection: &Connection, guild_id: GuildId) -> Result<Vec<StoredTable>> {
+        let mut statement = connection.prepare(
+            "SELECT name, owner_id, content FROM random_tables WHERE guild_id = ?1 ORDER BY name",
+        )?;
+        let rows = statement
+            .query_map(params![guild_id.get() as i64], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        rows.into_iter()
+            .map(|(name, owner_id, content)| {
+                Ok(StoredTable {
+                    guild_id,
+                    name,
+                    owner_id: UserId::new(owner_id as u64),
+                    table: serde_json::from_str(&content)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Deletes the table in the guild.
+    /// Returns `false` if the guild does not have it.
+    pub fn delete(connection: &Connection, guild_id: GuildId, name: &str) -> Result<bool> {
+        let deleted = connection.execute(
+            "DELETE FROM random_tables WHERE guild_id = ?1 AND name = ?2",
+            params![guild_id.get() as i64, name],
+        )?;
+        Ok(deleted > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random_table::{parse, TableFormat};
+    use crate::storage::open_in_memory;
+
+    #[test]
+    fn save_list_and_delete() {
+        let connection = open_in_memory();
+        let guild_id = GuildId::new(1);
+
+        let stored = |name: &str, source: &str| StoredTable {
+            guild_id,
+            name: name.to_string(),
+            owner_id: UserId::new(2),
+            table: parse(source, TableFormat::Text).unwrap(),
+        };
+        stored("weather", "Rain\nFog").save(&connection).unwrap();
+        stored("Encounters", "Cultist").save(&connection).unwrap();
+
+        // The name is case-insensitive, and the table is overwritten.
+        let updated = stored("Weather", "Rain\nFog\nClear");
+        updated.save(&connection).unwrap();
+        assert_eq!(
+            StoredTable::find(&connection, guild_id, "WEATHER").unwrap(),
+            Some(updated)
+        );
+
+        let names = StoredTable::list(&connection, guild_id)
+            .unwrap()
+            .into_iter()
+            .map(|stored| stored.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Encounters", "Weather"]);
+        assert!(StoredTable::list(&connection, GuildId::new(3))
+            .unwrap()
+            .is_empty());
+
+        assert!(StoredTable::delete(&connection, guild_id, "encounters").unwrap());
+        assert!(!StoredTable::delete(&connection, guild_id, "encounters").unwrap());
+    }
+}
@@ -0,0 +1,87 @@
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use serenity::model::id::UserId;
+
+/// A result of a roll which only the user who rolled it can see until it is revealed.
+#[derive(Clone, Debug)]
+pub struct SecretRoll {
+    pub id: i64,
+    pub owner_id: UserId,
+    /// The result serialized as a JSON of the embed.
+    pub embed: String,
+    pub revealed: bool,
+}
+
+impl SecretRoll {
+    /// Saves a result and returns its identifier.
+    pub fn insert(connection: &Connection, owner_id: UserId, embed: &str) -> Result<i64> {
+        connection.execute(
+            "INSERT INTO secret_rolls (owner_id, embed) VALUES (?1, ?2)",
+            params![owner_id.get() as i64, embed],
+        )?;
+        Ok(connection.last_insert_rowid())
+    }
+
+    /// Finds a result by its identifier.
+    pub fn find(connection: &Connection, id: i64) -> Result<Option<SecretRoll>> {
+        let roll = connection
+            .query_row(
+                "SELECT owner_id, embed, revealed FROM secret_rolls WHERE id = ?1",
+                params![id],
+                |row| {
+                    Ok(SecretRoll {
+                        id,
+                        owner_id: UserId::new(row.get::<_, i64>(0)? as u64),
+                        embed: row.get(1)?,
+                        revealed: row.get(2)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(roll)
+    }
+
+    /// Replaces the result (ex. when the roll is pushed).
+    pub fn update(connection: &Connection, id: i64, embed: &str) -> Result<()> {
+        connection.execute(
+            "UPDATE secret_rolls SET embed = ?2 WHERE id = ?1 AND revealed = 0",
+            params![id, embed],
+        )?;
+        Ok(())
+    }
+
+    /// Marks the result as revealed.
+    /// Returns `false` if it has already been revealed.
+    pub fn reveal(connection: &Connection, id: i64) -> Result<bool> {
+        let updated = connection.execute(
+            "UPDATE secret_rolls SET revealed = 1 WHERE id = ?1 AND revealed = 0",
+            params![id],
+        )?;
+        Ok(updated > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::open_in_memory;
+
+    #[test]
+    fn reveal_only_once() {
+        let connection = open_in_memory();
+
+        let id = SecretRoll::insert(&connection, UserId::new(1), "{}").unwrap();
+        SecretRoll::update(&connection, id, r#"{"title":"pushed"}"#).unwrap();
+
+        assert!(SecretRoll::reveal(&connection, id).unwrap());
+        assert!(!SecretRoll::reveal(&connection, id).unwrap());
+
+        // The result cannot be changed after it is revealed.
+        SecretRoll::update(&connection, id, "{}").unwrap();
+
+        let roll = SecretRoll::find(&connection, id).unwrap().unwrap();
+        assert_eq!(roll.owner_id, UserId::new(1));
+        assert_eq!(roll.embed, r#"{"title":"pushed"}"#);
+        assert!(roll.revealed);
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use serenity::model::id::GuildId;
+
+/// A seed of the dice in a guild.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GuildSeed {
+    pub seed: u64,
+    /// The number of the words drawn from the seeded source so far.
+    pub position: u64,
+}
+
+impl GuildSeed {
+    /// Gets the seed of the guild if it has one.
+    pub fn get(connection: &Connection, guild_id: GuildId) -> Result<Option<GuildSeed>> {
+        let seed = connection
+            .query_row(
+                "SELECT seed, position FROM guild_seeds WHERE guild_id = ?1",
+                params![guild_id.get() as i64],
+                |row| {
+                    Ok(GuildSeed {
+                        seed: row.get::<_, i64>(0)? as u64,
+                        position: row.get::<_, i64>(1)? as u64,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(seed)
+    }
+
+    /// Sets the seed of the guild. The dice start from the beginning of the seed.
+    pub fn set(connection: &Connection, guild_id: GuildId, seed: u64) -> Result<()> {
+        connection.execute(
+            "INSERT INTO guild_seeds (guild_id, seed) VALUES (?1, ?2)
+             ON CONFLICT (guild_id) DO UPDATE SET seed = excluded.seed, position = 0",
+            params![guild_id.get() as i64, seed as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Records how far the dice of the guild have been drawn.
+    pub fn advance(connection: &Connection, guild_id: GuildId, position: u64) -> Result<()> {
+        connection.execute(
+            "UPDATE guild_seeds SET position = ?2 WHERE guild_id = ?1",
+            params![guild_id.get() as i64, position as i64],
+        )?;
+        Ok(())
+    }
+
+    /// Removes the seed of the guild and returns it.
+    pub fn clear(connection: &Connection, guild_id: GuildId) -> Result<Option<GuildSeed>> {
+        let seed = GuildSeed::get(connection, guild_id)?;
+        connection.execute(
+            "DELETE FROM guild_seeds WHERE guild_id = ?1",
+            params![guild_id.get() as i64],
+        )?;
+        Ok(seed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::open_in_memory;
+
+    #[test]
+    fn set_advance_and_clear() {
+        let connection = open_in_memory();
+        let guild_id = GuildId::new(1);
+
+        assert_eq!(GuildSeed::get(&connection, guild_id).unwrap(), None);
+
+        GuildSeed::set(&connection, guild_id, u64::MAX).unwrap();
+        GuildSeed::advance(&connection, guild_id, 12).unwrap();
+        assert_eq!(
+            GuildSeed::get(&connection, guild_id).unwrap(),
+            Some(GuildSeed {
+                seed: u64::MAX,
+                position: 12
+            })
+        );
+
+        // Setting a seed again starts the dice over.
+        GuildSeed::set(&connection, guild_id, 42).unwrap();
+        assert_eq!(
+            GuildSeed::get(&connection, guild_id).unwrap(),
+            Some(GuildSeed {
+                seed: 42,
+                position: 0
+            })
+        );
+
+        assert_eq!(
+            GuildSeed::clear(&connection, guild_id)
+                .unwrap()
+                .map(|seed| seed.seed),
+            Some(42)
+        );
+        assert_eq!(GuildSeed::get(&connection, guild_id).unwrap(), None);
+    }
+}
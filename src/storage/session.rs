@@ -0,0 +1,287 @@
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serenity::model::id::{ChannelId, GuildId, UserId};
+
+use crate::rules::SkillTier;
+
+/// A session of a channel. The results of the commands are recorded while it is running.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Session {
+    pub id: i64,
+    pub guild_id: Option<GuildId>,
+    pub channel_id: ChannelId,
+    /// When the session started (UTC, ex. `2024-01-01 12:00:00`).
+    pub started_at: String,
+    /// When the session ended. `None` while it is running.
+    pub ended_at: Option<String>,
+}
+
+/// A result of a command to be recorded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RollRecord {
+    /// A name of the one who rolls the dice (ex. the nickname or the character name).
+    pub roller: String,
+    /// What is rolled (ex. `2d6 + 1`, `Spot Hidden (60)`).
+    pub expression: String,
+    /// The raw dice (ex. `[3, 5] + 1`).
+    pub dice: String,
+    /// The result (ex. `9`, `Hard`).
+    pub result: String,
+    pub tier: Option<SkillTier>,
+}
+
+/// A result recorded in a session.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoggedRoll {
+    /// When the command was used (UTC, ex. `2024-01-01 12:00:00`).
+    pub rolled_at: String,
+    pub user_id: UserId,
+    /// A name of the command (ex. `sk7`).
+    pub command: String,
+    /// Whether it was rolled in secret.
+    pub secret: bool,
+    pub record: RollRecord,
+}
+
+/// The columns read by `Session::from_row`.
+const SESSION_COLUMNS: &str = "id, guild_id, channel_id, started_at, ended_at";
+
+impl Session {
+    /// Starts a session in the channel.
+    /// Returns `None` if a session is already running there.
+    pub fn start(
+        connection: &Connection,
+        guild_id: Option<GuildId>,
+        channel_id: ChannelId,
+    ) -> Result<Option<Session>> {
+        if Session::running(connection, channel_id)?.is_some() {
+            return Ok(None);
+        }
+
+        connection.execute(
+            "INSERT INTO sessions (guild_id, channel_id) VALUES (?1, ?2)",
+            params![
+                guild_id.map(|guild_id| guild_id.get() as i64),
+                channel_id.get() as i64
+            ],
+        )?;
+        Session::find(connection, connection.last_insert_rowid())
+    }
+
+    /// Finds the session running in the channel.
+    pub fn running(connection: &Connection, channel_id: ChannelId) -> Result<Option<Session>> {
+        let session = connection
+            .query_row(
+                &format!(
+                    "SELECT {} FROM sessions WHERE channel_id = ?1 AND ended_at IS NULL",
+                    SESSION_COLUMNS
+                ),
+                params![channel_id.get() as i64],
+                Session::from_row,
+            )
+            .optional()?;
+        Ok(session)
+    }
+
+    /// Finds the latest session of the channel, which may be still running.
+    pub fn latest(connection: &Connection, channel_id: ChannelId) -> Result<Option<Session>> {
+        let session = connection
+            .query_row(
+                &format!(
+                    "SELECT {} FROM sessions WHERE channel_id = ?1 ORDER BY id DESC LIMIT 1",
+                    SESSION_COLUMNS
+                ),
+                params![channel_id.get() as i64],
+                Session::from_row,
+            )
+            .optional()?;
+        Ok(session)
+    }
+
+    /// Ends the session running in the channel.
+    /// Returns `None` if no session is running there.
+    pub fn end(connection: &Connection, channel_id: ChannelId) -> Result<Option<Session>> {
+        let session = match Session::running(connection, channel_id)? {
+            Some(session) => session,
+            None => return Ok(None),
+        };
+
+        connection.execute(
+            "UPDATE sessions SET ended_at = CURRENT_TIMESTAMP WHERE id = ?1",
+            params![session.id],
+        )?;
+        Session::find(connection, session.id)
+    }
+
+    /// Records the result if a session is running in the channel.
+    /// Returns `false` if no session is running there.
+    pub fn record(
+        connection: &Connection,
+        channel_id: ChannelId,
+        user_id: UserId,
+        command: &str,
+        secret: bool,
+        record: &RollRecord,
+    ) -> Result<bool> {
+        let session = match Session::running(connection, channel_id)? {
+            Some(session) => session,
+            None => return Ok(false),
+        };
+
+        connection.execute(
+            "INSERT INTO session_rolls
+                (session_id, user_id, command, secret, roller, expression, dice, result, tier)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                session.id,
+                user_id.get() as i64,
+                command,
+                secret,
+                record.roller,
+                record.expression,
+                record.dice,
+                record.result,
+                record.tier.map(|tier| tier.id()),
+            ],
+        )?;
+        Ok(true)
+    }
+
+    /// Gets the results recorded in the session in the order they were rolled.
+    pub fn rolls(&self, connection: &Connection) -> Result<Vec<LoggedRoll>> {
+        let mut statement = connection.prepare(
+            "SELECT rolled_at, user_id, command, secret, roller, expression, dice, result, tier
+                FROM session_rolls WHERE session_id = ?1 ORDER BY id",
+        )?;
+        let rolls = statement
+            .query_map(params![self.id], |row| {
+                Ok(LoggedRoll {
+                    rolled_at: row.get(0)?,
+                    user_id: UserId::new(row.get::<_, i64>(1)? as u64),
+                    command: row.get(2)?,
+                    secret: row.get(3)?,
+                    record: RollRecord {
+                        roller: row.get(4)?,
+                        expression: row.get(5)?,
+                        dice: row.get(6)?,
+                        result: row.get(7)?,
+                        tier: row
+                            .get::<_, Option<String>>(8)?
+                            .and_then(|tier| SkillTier::from_id(&tier)),
+                    },
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rolls)
+    }
+
+    fn find(connection: &Connection, id: i64) -> Result<Option<Session>> {
+        let session = connection
+            .query_row(
+                &format!("SELECT {} FROM sessions WHERE id = ?1", SESSION_COLUMNS),
+                params![id],
+                Session::from_row,
+            )
+            .optional()?;
+        Ok(session)
+    }
+
+    fn from_row(row: &Row) -> rusqlite::Result<Session> {
+        Ok(Session {
+            id: row.get(0)?,
+            guild_id: row
+                .get::<_, Option<i64>>(1)?
+                .map(|guild_id| GuildId::new(guild_id as u64)),
+            channel_id: ChannelId::new(row.get::<_, i64>(2)? as u64),
+            started_at: row.get(3)?,
+            ended_at: row.get(4)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::open_in_memory;
+
+    fn record(expression: &str, tier: Option<SkillTier>) -> RollRecord {
+        RollRecord {
+            roller: "Harvey".to_string(),
+            expression: expression.to_string(),
+            dice: "42".to_string(),
+            result: "42".to_string(),
+            tier,
+        }
+    }
+
+    #[test]
+    fn records_only_while_running() {
+        let connection = open_in_memory();
+        let channel = ChannelId::new(10);
+        let other = ChannelId::new(20);
+        let user = UserId::new(1);
+
+        // Nothing is recorded before the session starts.
+        assert!(!Session::record(
+            &connection,
+            channel,
+            user,
+            "roll",
+            false,
+            &record("1d100", None)
+        )
+        .unwrap());
+
+        let session = Session::start(&connection, Some(GuildId::new(1)), channel)
+            .unwrap()
+            .unwrap();
+        assert!(Session::start(&connection, None, channel)
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            Session::running(&connection, channel).unwrap(),
+            Some(session.clone())
+        );
+        assert!(Session::running(&connection, other).unwrap().is_none());
+
+        let spot_hidden = record("Spot Hidden (60)", Some(SkillTier::Hard));
+        assert!(Session::record(&connection, channel, user, "sk7", true, &spot_hidden).unwrap());
+        assert!(!Session::record(
+            &connection,
+            other,
+            user,
+            "roll",
+            false,
+            &record("1d6", None)
+        )
+        .unwrap());
+
+        let ended = Session::end(&connection, channel).unwrap().unwrap();
+        assert_eq!(ended.id, session.id);
+        assert!(ended.ended_at.is_some());
+        assert!(Session::end(&connection, channel).unwrap().is_none());
+        assert!(!Session::record(
+            &connection,
+            channel,
+            user,
+            "roll",
+            false,
+            &record("1d6", None)
+        )
+        .unwrap());
+
+        let rolls = ended.rolls(&connection).unwrap();
+        assert_eq!(rolls.len(), 1);
+        assert_eq!(rolls[0].command, "sk7");
+        assert!(rolls[0].secret);
+        assert_eq!(rolls[0].record, spot_hidden);
+
+        // A new session can be started after the previous one ended.
+        let next = Session::start(&connection, None, channel).unwrap().unwrap();
+        assert_eq!(
+            Session::latest(&connection, channel).unwrap(),
+            Some(next.clone())
+        );
+        assert!(next.rolls(&connection).unwrap().is_empty());
+    }
+}
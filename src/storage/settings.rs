@@ -0,0 +1,75 @@
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use serenity::model::id::GuildId;
+
+use crate::rules::GameSystem;
+
+/// Settings of a guild.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GuildSettings {
+    /// A game system which `/skill` and `/op` follow.
+    pub system: GameSystem,
+}
+
+impl GuildSettings {
+    /// Gets the settings of the guild. The default one is returned if nothing is set.
+    pub fn get(connection: &Connection, guild_id: GuildId) -> Result<GuildSettings> {
+        let system: Option<String> = connection
+            .query_row(
+                "SELECT system FROM guild_settings WHERE guild_id = ?1",
+                params![guild_id.get() as i64],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(GuildSettings {
+            system: system
+                .and_then(|system| GameSystem::from_id(&system))
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Sets the game system of the guild.
+    pub fn set_system(
+        connection: &Connection,
+        guild_id: GuildId,
+        system: GameSystem,
+    ) -> Result<()> {
+        connection.execute(
+            "INSERT INTO guild_settings (guild_id, system) VALUES (?1, ?2)
+             ON CONFLICT (guild_id) DO UPDATE SET system = excluded.system",
+            params![guild_id.get() as i64, system.id()],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::open_in_memory;
+
+    #[test]
+    fn set_system() {
+        let connection = open_in_memory();
+        let guild_id = GuildId::new(1);
+
+        assert_eq!(
+            GuildSettings::get(&connection, guild_id).unwrap().system,
+            GameSystem::Coc6th
+        );
+
+        GuildSettings::set_system(&connection, guild_id, GameSystem::Coc7th).unwrap();
+        GuildSettings::set_system(&connection, guild_id, GameSystem::DeltaGreen).unwrap();
+        assert_eq!(
+            GuildSettings::get(&connection, guild_id).unwrap().system,
+            GameSystem::DeltaGreen
+        );
+        assert_eq!(
+            GuildSettings::get(&connection, GuildId::new(2))
+                .unwrap()
+                .system,
+            GameSystem::Coc6th
+        );
+    }
+}
@@ -0,0 +1,132 @@
+use anyhow::Result;
+use rusqlite::{params, Connection};
+use serenity::model::id::{GuildId, UserId};
+
+use crate::rules::{GameSystem, SkillOutcome, SkillTier};
+
+/// Outcomes of the skill rolls made by a user or everyone in a guild.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RollStats {
+    /// The number of the rolls for each system and tier.
+    pub tiers: Vec<(GameSystem, SkillTier, u32)>,
+    /// The d100 results rolled without bonus or penalty dice, which must be uniform.
+    pub d100: Vec<i32>,
+}
+
+impl RollStats {
+    /// Records the outcome of a skill roll.
+    /// `guild_id` is `None` for the rolls outside of the guilds.
+    pub fn record(
+        connection: &Connection,
+        guild_id: Option<GuildId>,
+        user_id: UserId,
+        system: GameSystem,
+        outcome: &SkillOutcome,
+        bonus: i32,
+    ) -> Result<()> {
+        connection.execute(
+            "INSERT INTO roll_stats (guild_id, user_id, system, tier, rolled, bonus)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                guild_id.map(|guild_id| guild_id.get() as i64),
+                user_id.get() as i64,
+                system.id(),
+                outcome.tier.id(),
+                outcome.rolled,
+                bonus
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Loads the stats of the user in the guild, or of everyone in the guild if `user_id` is `None`.
+    pub fn load(
+        connection: &Connection,
+        guild_id: Option<GuildId>,
+        user_id: Option<UserId>,
+    ) -> Result<RollStats> {
+        let guild_id = guild_id.map(|guild_id| guild_id.get() as i64);
+        let user_id = user_id.map(|user_id| user_id.get() as i64);
+
+        let mut statement = connection.prepare(
+            "SELECT system, tier, COUNT(*) FROM roll_stats
+                WHERE guild_id IS ?1 AND (?2 IS NULL OR user_id = ?2)
+                GROUP BY system, tier",
+        )?;
+        let mut tiers = statement
+            .query_map(params![guild_id, user_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, u32>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?
+            .into_iter()
+            .filter_map(|(system, tier, count)| {
+                Some((
+                    GameSystem::from_id(&system)?,
+                    SkillTier::from_id(&tier)?,
+                    count,
+                ))
+            })
+            .collect::<Vec<_>>();
+        tiers.sort_by_key(|(system, tier, _)| (system.id(), std::cmp::Reverse(tier.rank())));
+
+        let mut statement = connection.prepare(
+            "SELECT rolled FROM roll_stats
+                WHERE guild_id IS ?1 AND (?2 IS NULL OR user_id = ?2) AND bonus = 0",
+        )?;
+        let d100 = statement
+            .query_map(params![guild_id, user_id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(RollStats { tiers, d100 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::open_in_memory;
+
+    #[test]
+    fn load_per_user_and_guild() {
+        let connection = open_in_memory();
+        let guild = Some(GuildId::new(1));
+        let alice = UserId::new(10);
+        let bob = UserId::new(20);
+
+        let roll = |guild_id, user_id, system: GameSystem, rolled: i32, bonus| {
+            let outcome = system.rule().evaluate(50, rolled, rolled.to_string());
+            RollStats::record(&connection, guild_id, user_id, system, &outcome, bonus).unwrap();
+        };
+        roll(guild, alice, GameSystem::Coc6th, 3, 0);
+        roll(guild, alice, GameSystem::Coc6th, 40, 0);
+        roll(guild, alice, GameSystem::Coc7th, 99, 1);
+        roll(guild, bob, GameSystem::Coc6th, 70, 0);
+        roll(None, alice, GameSystem::Coc6th, 20, 0);
+
+        let stats = RollStats::load(&connection, guild, Some(alice)).unwrap();
+        assert_eq!(
+            stats.tiers,
+            vec![
+                (GameSystem::Coc6th, SkillTier::Critical, 1),
+                (GameSystem::Coc6th, SkillTier::Success, 1),
+                (GameSystem::Coc7th, SkillTier::Failure, 1),
+            ]
+        );
+        // The roll with a bonus die is not a fair d100.
+        assert_eq!(stats.d100, vec![3, 40]);
+
+        let everyone = RollStats::load(&connection, guild, None).unwrap();
+        assert_eq!(everyone.d100.len(), 3);
+
+        let direct = RollStats::load(&connection, None, Some(alice)).unwrap();
+        assert_eq!(direct.d100, vec![20]);
+        assert_eq!(
+            RollStats::load(&connection, guild, Some(UserId::new(30))).unwrap(),
+            RollStats::default()
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::vitals::Vitals;
+
+impl Vitals {
+    /// Loads the vitals of the character.
+    /// Returns the default one if nothing has changed since the character was created.
+    pub fn load(connection: &Connection, character_id: i64) -> Result<Vitals> {
+        let vitals = connection
+            .query_row(
+                "SELECT hp, mp, san, major_wound, san_day_start, san_lost_today
+                    FROM character_vitals WHERE character_id = ?1",
+                params![character_id],
+                |row| {
+                    Ok(Vitals {
+                        hp: row.get(0)?,
+                        mp: row.get(1)?,
+                        san: row.get(2)?,
+                        major_wound: row.get(3)?,
+                        san_day_start: row.get(4)?,
+                        san_lost_today: row.get(5)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(vitals.unwrap_or_default())
+    }
+
+    /// Saves the vitals of the character.
+    pub fn save(&self, connection: &Connection, character_id: i64) -> Result<()> {
+        connection.execute(
+            "INSERT INTO character_vitals
+                (character_id, hp, mp, san, major_wound, san_day_start, san_lost_today)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+             ON CONFLICT (character_id) DO UPDATE SET
+                hp = excluded.hp,
+                mp = excluded.mp,
+                san = excluded.san,
+                major_wound = excluded.major_wound,
+                san_day_start = excluded.san_day_start,
+                san_lost_today = excluded.san_lost_today",
+            params![
+                character_id,
+                self.hp,
+                self.mp,
+                self.san,
+                self.major_wound,
+                self.san_day_start,
+                self.san_lost_today
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::character::Character;
+    use crate::storage::open_in_memory;
+    use serenity::model::id::UserId;
+
+    #[test]
+    fn save_and_load() {
+        let mut connection = open_in_memory();
+        let owner_id = UserId::new(1);
+        let character = Character::insert(
+            &mut connection,
+            owner_id,
+            "Harvey Walters",
+            "7th",
+            &[("HP", 12), ("SAN", 60)],
+            &[],
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            Vitals::load(&connection, character.id).unwrap(),
+            Vitals::default()
+        );
+
+        let mut vitals = Vitals::default();
+        vitals.damage(12, 6);
+        vitals.lose_san(60, 5);
+        vitals.save(&connection, character.id).unwrap();
+        vitals.heal(12, 1);
+        vitals.save(&connection, character.id).unwrap();
+        assert_eq!(Vitals::load(&connection, character.id).unwrap(), vitals);
+
+        // The vitals are deleted with the character.
+        Character::delete(&connection, owner_id, "Harvey Walters").unwrap();
+        assert_eq!(
+            Vitals::load(&connection, character.id).unwrap(),
+            Vitals::default()
+        );
+    }
+}
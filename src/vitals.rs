@@ -0,0 +1,253 @@
+/// A loss of SAN at once which causes a temporary insanity.
+pub const TEMPORARY_INSANITY_LOSS: i32 = 5;
+
+/// The maximum SAN before it is reduced by Cthulhu Mythos.
+pub const MAX_SAN: i32 = 99;
+
+/// A condition caused by a change of HP or SAN following Call of Cthulhu 7th Edition.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Condition {
+    /// Damage of half the maximum HP or more at once.
+    MajorWound,
+    /// HP reached 0 without a major wound.
+    Unconscious,
+    /// HP reached 0 with a major wound.
+    Dying,
+    /// Damage of the maximum HP or more at once.
+    Dead,
+    /// 5 SAN or more lost at once.
+    TemporaryInsanity,
+    /// A fifth of SAN lost in a game day.
+    IndefiniteInsanity,
+    /// SAN reached 0.
+    PermanentInsanity,
+}
+
+impl Condition {
+    /// Gets a label of the condition shown in the embeds.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Condition::MajorWound => ":drop_of_blood: **Major Wound**",
+            Condition::Unconscious => ":zzz: **Unconscious**",
+            Condition::Dying => ":skull: **Dying**",
+            Condition::Dead => ":coffin: **Dead**",
+            Condition::TemporaryInsanity => ":warning: **Temporary insanity**",
+            Condition::IndefiniteInsanity => ":warning: **Indefinite insanity**",
+            Condition::PermanentInsanity => ":skull: **Permanent insanity**",
+        }
+    }
+
+    /// Explains what happens next.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Condition::MajorWound => {
+                "Lost half the maximum HP or more at once. Make a CON roll or fall unconscious."
+            }
+            Condition::Unconscious => "HP reached 0. The investigator falls unconscious.",
+            Condition::Dying => {
+                "HP reached 0 with a major wound. Make a CON roll at the end of each round or die."
+            }
+            Condition::Dead => "Lost the maximum HP or more at once.",
+            Condition::TemporaryInsanity => "Lost 5 or more SAN at once. Make an INT roll.",
+            Condition::IndefiniteInsanity => "Lost a fifth of SAN in this game day.",
+            Condition::PermanentInsanity => "SAN reached 0.",
+        }
+    }
+}
+
+/// The current HP, MP and SAN of a character.
+/// `None` means the value has not changed from the one on the sheet.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Vitals {
+    pub hp: Option<i32>,
+    pub mp: Option<i32>,
+    pub san: Option<i32>,
+    pub major_wound: bool,
+    /// SAN at the start of the game day.
+    pub san_day_start: Option<i32>,
+    /// SAN lost in the game day.
+    pub san_lost_today: i32,
+}
+
+impl Vitals {
+    /// Applies the damage and returns the conditions caused by it.
+    pub fn damage(&mut self, max_hp: i32, amount: i32) -> Vec<Condition> {
+        let amount = amount.max(0);
+        let hp = (self.hp.unwrap_or(max_hp) - amount).max(0);
+        self.hp = Some(hp);
+
+        let mut conditions = Vec::new();
+        if amount == 0 {
+            return conditions;
+        }
+        if amount >= max_hp {
+            conditions.push(Condition::Dead);
+            return conditions;
+        }
+        if amount * 2 >= max_hp {
+            self.major_wound = true;
+            conditions.push(Condition::MajorWound);
+        }
+        if hp == 0 {
+            conditions.push(if self.major_wound {
+                Condition::Dying
+            } else {
+                Condition::Unconscious
+            });
+        }
+        conditions
+    }
+
+    /// Heals HP up to the maximum. The major wound is cured when HP is fully recovered.
+    pub fn heal(&mut self, max_hp: i32, amount: i32) {
+        let hp = (self.hp.unwrap_or(max_hp) + amount.max(0)).min(max_hp);
+        self.hp = Some(hp);
+        if hp >= max_hp {
+            self.major_wound = false;
+        }
+    }
+
+    /// Gets the lasting conditions caused by the damage so far.
+    pub fn hp_conditions(&self, max_hp: i32) -> Vec<Condition> {
+        let mut conditions = Vec::new();
+        if self.major_wound {
+            conditions.push(Condition::MajorWound);
+        }
+        if self.hp.unwrap_or(max_hp) == 0 {
+            conditions.push(if self.major_wound {
+                Condition::Dying
+            } else {
+                Condition::Unconscious
+            });
+        }
+        conditions
+    }
+
+    /// Spends MP. It cannot go below 0.
+    pub fn spend_mp(&mut self, max_mp: i32, amount: i32) {
+        self.mp = Some((self.mp.unwrap_or(max_mp) - amount.max(0)).max(0));
+    }
+
+    /// Recovers MP up to the maximum.
+    pub fn recover_mp(&mut self, max_mp: i32, amount: i32) {
+        self.mp = Some((self.mp.unwrap_or(max_mp) + amount.max(0)).min(max_mp));
+    }
+
+    /// Loses SAN and returns the insanities caused by it.
+    /// `initial_san` is SAN on the sheet.
+    pub fn lose_san(&mut self, initial_san: i32, amount: i32) -> Vec<Condition> {
+        let san = self.san.unwrap_or(initial_san);
+        let day_start = *self.san_day_start.get_or_insert(san);
+
+        let new_san = (san - amount.max(0)).max(0);
+        let lost = san - new_san;
+        self.san = Some(new_san);
+
+        let before = self.san_lost_today;
+        self.san_lost_today += lost;
+
+        let mut conditions = Vec::new();
+        if lost >= TEMPORARY_INSANITY_LOSS {
+            conditions.push(Condition::TemporaryInsanity);
+        }
+        let threshold = day_start / 5;
+        if threshold > 0 && before < threshold && self.san_lost_today >= threshold {
+            conditions.push(Condition::IndefiniteInsanity);
+        }
+        if lost > 0 && new_san == 0 {
+            conditions.push(Condition::PermanentInsanity);
+        }
+        conditions
+    }
+
+    /// Gains SAN up to the maximum (99 - Cthulhu Mythos).
+    pub fn gain_san(&mut self, initial_san: i32, max_san: i32, amount: i32) {
+        self.san = Some((self.san.unwrap_or(initial_san) + amount.max(0)).min(max_san));
+    }
+
+    /// Starts a new game day. The loss for the indefinite insanity is counted from the current SAN.
+    pub fn new_day(&mut self, initial_san: i32) {
+        self.san_day_start = Some(self.san.unwrap_or(initial_san));
+        self.san_lost_today = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn damage_and_heal() {
+        let mut vitals = Vitals::default();
+
+        assert!(vitals.damage(12, 3).is_empty());
+        assert_eq!(vitals.hp, Some(9));
+
+        assert_eq!(vitals.damage(12, 6), vec![Condition::MajorWound]);
+        assert!(vitals.major_wound);
+
+        // HP does not go below 0.
+        assert_eq!(vitals.damage(12, 5), vec![Condition::Dying]);
+        assert_eq!(vitals.hp, Some(0));
+        assert_eq!(
+            vitals.hp_conditions(12),
+            vec![Condition::MajorWound, Condition::Dying]
+        );
+
+        vitals.heal(12, 5);
+        assert_eq!(vitals.hp, Some(5));
+        assert!(vitals.major_wound);
+        vitals.heal(12, 20);
+        assert_eq!(vitals.hp, Some(12));
+        assert!(!vitals.major_wound);
+        assert!(vitals.hp_conditions(12).is_empty());
+
+        assert_eq!(vitals.damage(12, 5), vec![]);
+        assert_eq!(vitals.damage(12, 5), vec![]);
+        assert_eq!(vitals.damage(12, 2), vec![Condition::Unconscious]);
+
+        let mut vitals = Vitals::default();
+        assert_eq!(vitals.damage(12, 12), vec![Condition::Dead]);
+    }
+
+    #[test]
+    fn magic_points() {
+        let mut vitals = Vitals::default();
+        vitals.spend_mp(10, 4);
+        assert_eq!(vitals.mp, Some(6));
+        vitals.spend_mp(10, 8);
+        assert_eq!(vitals.mp, Some(0));
+        vitals.recover_mp(10, 15);
+        assert_eq!(vitals.mp, Some(10));
+    }
+
+    #[test]
+    fn insanities() {
+        let mut vitals = Vitals::default();
+
+        assert!(vitals.lose_san(60, 3).is_empty());
+        assert_eq!(vitals.lose_san(60, 5), vec![Condition::TemporaryInsanity]);
+        assert_eq!(vitals.san, Some(52));
+
+        // A fifth of 60 is 12.
+        assert_eq!(vitals.lose_san(60, 4), vec![Condition::IndefiniteInsanity]);
+        assert!(vitals.lose_san(60, 1).is_empty());
+
+        vitals.new_day(60);
+        assert_eq!(vitals.san_day_start, Some(47));
+        assert_eq!(vitals.san_lost_today, 0);
+
+        vitals.gain_san(60, 50, 10);
+        assert_eq!(vitals.san, Some(50));
+
+        assert_eq!(
+            vitals.lose_san(60, 99),
+            vec![
+                Condition::TemporaryInsanity,
+                Condition::IndefiniteInsanity,
+                Condition::PermanentInsanity
+            ]
+        );
+        assert_eq!(vitals.san, Some(0));
+    }
+}